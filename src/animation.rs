@@ -0,0 +1,228 @@
+use std::{
+    io::{stdin, stdout, Read, Write},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use crate::simulation::{Control, Observer, Simulation};
+
+/** Something that can be drawn as a grid of characters, in screen coordinates (y down) */
+pub trait Scene {
+    /** Where the action is, so the viewport can follow it */
+    fn focus(&self) -> (i32, i32);
+
+    fn render(&self, viewport: &Viewport) -> String;
+
+    /** Inclusive `((min_x, max_x), (min_y, max_y))` of a scene that doesn't extend forever */
+    fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        None
+    }
+}
+
+/** The visible window onto a scene */
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    pub left: i32,
+    pub top: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Viewport {
+    pub fn new(width: u32, height: u32) -> Viewport {
+        Viewport {
+            left: 0,
+            top: 0,
+            width,
+            height,
+        }
+    }
+
+    pub fn right(&self) -> i32 {
+        self.left + self.width as i32 - 1
+    }
+
+    pub fn bottom(&self) -> i32 {
+        self.top + self.height as i32 - 1
+    }
+
+    /** Scroll just enough to keep `(x, y)` out of the outer quarter of the view */
+    pub fn follow(&mut self, (x, y): (i32, i32)) {
+        let margin_x = self.width as i32 / 4;
+        let margin_y = self.height as i32 / 4;
+
+        if x < self.left + margin_x {
+            self.left = x - margin_x;
+        } else if x > self.right() - margin_x {
+            self.left = x + margin_x - self.width as i32 + 1;
+        }
+
+        if y < self.top + margin_y {
+            self.top = y - margin_y;
+        } else if y > self.bottom() - margin_y {
+            self.top = y + margin_y - self.height as i32 + 1;
+        }
+    }
+
+    /** Keep the view inside `bounds`, pinning it to the top left if the scene is smaller */
+    pub fn clamp(&mut self, ((min_x, max_x), (min_y, max_y)): ((i32, i32), (i32, i32))) {
+        self.left = self.left.min(max_x - self.width as i32 + 1).max(min_x);
+        self.top = self.top.min(max_y - self.height as i32 + 1).max(min_y);
+    }
+}
+
+/** Puts the terminal into unbuffered, no-echo mode for as long as it's alive */
+struct RawMode {
+    enabled: bool,
+}
+
+impl RawMode {
+    fn enable() -> RawMode {
+        RawMode {
+            enabled: stty(&["-icanon", "-echo"]),
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if self.enabled {
+            stty(&["icanon", "echo"]);
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> bool {
+    Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+/**
+ * Observer that redraws the scene after every step.
+ *
+ * Keys: `space` pause/resume, `n` single step while paused, `+`/`-` change speed, `q` quit
+ */
+pub struct Animator {
+    fps: u32,
+    viewport: Viewport,
+    paused: bool,
+    steps: usize,
+    keys: Receiver<u8>,
+    _raw_mode: RawMode,
+}
+
+impl Animator {
+    pub fn new(fps: u32, viewport: Viewport) -> Animator {
+        let (sender, keys) = mpsc::channel();
+
+        // Reading stdin blocks, so keys are forwarded from a background thread
+        thread::spawn(move || {
+            let mut key = [0; 1];
+
+            while let Ok(1) = stdin().read(&mut key) {
+                if sender.send(key[0]).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Clear screen, hide cursor
+        print!("\x1b[2J\x1b[?25l");
+
+        Animator {
+            fps: fps.max(1),
+            viewport,
+            paused: false,
+            steps: 0,
+            keys,
+            _raw_mode: RawMode::enable(),
+        }
+    }
+
+    fn draw<S: Scene>(&mut self, scene: &S) {
+        self.viewport.follow(scene.focus());
+
+        if let Some(bounds) = scene.bounds() {
+            self.viewport.clamp(bounds);
+        }
+
+        let mut out = stdout().lock();
+        // Cursor home, frame, then clear anything left over below it
+        let _ = write!(
+            out,
+            "\x1b[H{}\x1b[J\nstep {} | {} fps{} | [space] pause [n] step [+/-] speed [q] quit\n",
+            scene.render(&self.viewport),
+            self.steps,
+            self.fps,
+            if self.paused { " | paused" } else { "" }
+        );
+        let _ = out.flush();
+    }
+
+    /** Apply a key press, returning `Some` if it decides what happens next */
+    fn handle_key(&mut self, key: u8) -> Option<Control> {
+        match key {
+            b' ' => {
+                self.paused = !self.paused;
+                (!self.paused).then_some(Control::Continue)
+            }
+            b'n' if self.paused => Some(Control::Continue),
+            b'+' | b'=' => {
+                self.fps = self.fps.saturating_mul(2);
+                None
+            }
+            b'-' => {
+                self.fps = (self.fps / 2).max(1);
+                None
+            }
+            b'q' => Some(Control::Stop),
+            _ => None,
+        }
+    }
+}
+
+impl<S: Simulation + Scene> Observer<S> for Animator {
+    fn after_step(&mut self, simulation: &S) -> Control {
+        self.steps += 1;
+        self.draw(simulation);
+
+        while let Ok(key) = self.keys.try_recv() {
+            if let Some(Control::Stop) = self.handle_key(key) {
+                return Control::Stop;
+            }
+        }
+
+        while self.paused {
+            self.draw(simulation);
+
+            match self.keys.recv() {
+                Ok(key) => {
+                    if let Some(control) = self.handle_key(key) {
+                        return control;
+                    }
+                }
+                // Input closed, nothing can unpause us
+                Err(_) => self.paused = false,
+            }
+        }
+
+        thread::sleep(Duration::from_secs(1) / self.fps);
+
+        Control::Continue
+    }
+}
+
+impl Drop for Animator {
+    fn drop(&mut self) {
+        // Show cursor again
+        print!("\x1b[?25h");
+        let _ = stdout().flush();
+    }
+}
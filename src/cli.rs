@@ -0,0 +1,64 @@
+use std::{collections::HashMap, str::FromStr};
+
+/** Command line arguments, as positionals, `--flag` / `--flag=value` options and `-abc` switches */
+pub struct Args {
+    positional: Vec<String>,
+    flags: HashMap<String, Option<String>>,
+    /** How many times each single letter switch was given, so `-vv` counts twice */
+    switches: HashMap<char, usize>,
+}
+
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> Args {
+        let mut positional = vec![];
+        let mut flags = HashMap::new();
+        let mut switches = HashMap::new();
+
+        for arg in args {
+            if let Some(flag) = arg.strip_prefix("--") {
+                match flag.split_once('=') {
+                    Some((name, value)) => flags.insert(name.to_string(), Some(value.to_string())),
+                    None => flags.insert(flag.to_string(), None),
+                };
+            } else if let Some(letters) = arg.strip_prefix('-').filter(|it| !it.is_empty()) {
+                for letter in letters.chars() {
+                    *switches.entry(letter).or_insert(0) += 1;
+                }
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        Args {
+            positional,
+            flags,
+            switches,
+        }
+    }
+
+    pub fn count(&self, switch: char) -> usize {
+        *self.switches.get(&switch).unwrap_or(&0)
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).and_then(|value| value.as_deref())
+    }
+
+    /** Parse the value of `--name=value`, falling back to `default` if the flag isn't given */
+    pub fn parse_value<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.value(name) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("Invalid value for --{}: {}", name, value)),
+            None => Ok(default),
+        }
+    }
+}
@@ -1,61 +1,410 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{self, Display},
+    io::BufRead,
+};
+
+use crate::{event, trace::Level};
+
 pub fn run(input: Vec<String>) {
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    match part1(&input) {
+        Ok(answer) => println!("Part 1: {}", answer),
+        Err(err) => eprintln!("Part 1: {}", err),
+    }
+
+    match part2(&input) {
+        Ok(answer) => println!("Part 2: {}", answer),
+        Err(err) => eprintln!("Part 2: {}", err),
+    }
 }
 
-fn part1(input: &[String]) -> i32 {
-    let mut max = 0;
-    let mut sum = 0;
+/**
+ * Aggregate inventories straight from `reader`, without holding them in memory. A line that
+ * can't be read is an error, rather than the end of the input
+ */
+pub fn stream(reader: impl BufRead, k: usize) {
+    let mut unreadable = None;
+    let lines = reader
+        .lines()
+        .enumerate()
+        .map_while(|(i, line)| match line {
+            Ok(line) => Some(line),
+            Err(err) => {
+                unreadable = Some(InventoryError::Unreadable {
+                    line: i + 1,
+                    reason: err.to_string(),
+                });
+                None
+            }
+        });
+    let aggregate = aggregate(lines, k);
+
+    match unreadable.map_or(aggregate, Err) {
+        Ok(aggregate) => {
+            println!("Elves: {}", aggregate.elves);
+            println!("Max:   {}", aggregate.max);
+            println!("Top {}:", k);
 
-    for line in input.iter() {
-        if line.is_empty() {
-            if sum > max {
-                max = sum;
+            for (total, index) in aggregate.top {
+                println!("  Elf #{:<8} {:>12}", index + 1, total);
             }
-            sum = 0;
-        } else {
-            sum += line.parse::<i32>().unwrap();
         }
+        Err(err) => eprintln!("{}", err),
     }
+}
+
+/** Print the `k` best stocked elves, followed by statistics across every elf */
+pub fn summary(input: Vec<String>, k: usize) {
+    let elves = match parse_input(&input) {
+        Ok(elves) => elves,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
 
-    if sum > max {
-        max = sum;
+    println!("Top {}:", k);
+    for elf in top(&elves, k) {
+        println!(
+            "  Elf #{:<4} {:>7} {:?}",
+            elf.index + 1,
+            elf.total(),
+            elf.items
+        );
     }
 
-    max
+    let summary = match Summary::new(&elves) {
+        Ok(Some(summary)) => summary,
+        Ok(None) => {
+            println!("No elves");
+            return;
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
+        }
+    };
+
+    println!();
+    println!("Elves:  {}", summary.count);
+    println!("Total:  {}", summary.total);
+    println!("Min:    {}", summary.min);
+    println!("Max:    {}", summary.max);
+    println!("Mean:   {:.1}", summary.mean);
+    println!("Median: {:.1}", summary.median);
+    println!("StdDev: {:.1}", summary.std_dev);
+    println!();
+    println!("Distribution:");
+
+    let widest = summary.histogram.iter().max().copied().unwrap_or(1).max(1);
+    for (i, count) in summary.histogram.iter().enumerate() {
+        let (from, to) = summary.bucket_range(i);
+        println!(
+            "  {:>7}-{:<7} {:>4} {}",
+            from,
+            to,
+            count,
+            "#".repeat(count * 40 / widest)
+        );
+    }
 }
 
-fn part2(input: &[String]) -> i32 {
-    let mut top3 = [0; 3];
+/** Why an inventory couldn't be read, parsed or added up */
+#[derive(Debug, PartialEq, Eq)]
+pub enum InventoryError {
+    InvalidNumber {
+        line: usize,
+        text: String,
+    },
+    /** An elf's total no longer fits in 64 bits */
+    Overflow {
+        line: usize,
+    },
+    /** The totals of several elves added together no longer fit in 64 bits */
+    SumOverflow,
+    /** The input couldn't be read as text */
+    Unreadable {
+        line: usize,
+        reason: String,
+    },
+}
 
-    let mut insert = |val: i32| {
-        // Find insertion point, shift values, insert
-        if val < top3[0] {
-            // NO-OP
-        } else if val < top3[1] {
-            top3[0] = val;
-        } else if val < top3[2] {
-            top3[0] = top3[1];
-            top3[1] = val;
-        } else {
-            top3[0] = top3[1];
-            top3[1] = top3[2];
-            top3[2] = val;
+impl Display for InventoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryError::InvalidNumber { line, text } => {
+                write!(f, "Line {}: '{}' isn't a calorie count", line, text)
+            }
+            InventoryError::Overflow { line } => {
+                write!(f, "Line {}: calorie total overflowed", line)
+            }
+            InventoryError::SumOverflow => write!(f, "Sum of calorie totals overflowed"),
+            InventoryError::Unreadable { line, reason } => {
+                write!(f, "Line {}: couldn't be read: {}", line, reason)
+            }
         }
-    };
+    }
+}
+
+/** One elf's inventory */
+#[derive(Debug)]
+pub struct Elf {
+    /** Position in the input, from 0 */
+    pub index: usize,
+    /** Calories of each item carried */
+    pub items: Vec<u64>,
+}
+
+impl Elf {
+    /** Can't overflow, parsing checks it */
+    pub fn total(&self) -> u64 {
+        self.items.iter().sum()
+    }
+}
+
+/** What's left of a stream of inventories once it's been aggregated */
+#[derive(Debug, PartialEq, Eq)]
+pub struct Aggregate {
+    pub elves: usize,
+    pub max: u64,
+    /** `(total, index)` of the best stocked elves, most first */
+    pub top: Vec<(u64, usize)>,
+}
+
+/** Keeps the `k` largest totals seen so far, in `O(k)` memory at most */
+struct TopK {
+    k: usize,
+    /** Min-heap, so the weakest entry is the one evicted. Ties go to whoever came first */
+    heap: BinaryHeap<Reverse<(u64, Reverse<usize>)>>,
+}
+
+impl TopK {
+    fn new(k: usize) -> TopK {
+        TopK {
+            k,
+            // Grown as needed past this, so a huge `k` doesn't allocate up front
+            heap: BinaryHeap::with_capacity(k.min(1024).saturating_add(1)),
+        }
+    }
+
+    fn push(&mut self, total: u64, index: usize) {
+        self.heap.push(Reverse((total, Reverse(index))));
+
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(u64, usize)> {
+        // Ascending order of `Reverse` is best first
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((total, Reverse(index)))| (total, index))
+            .collect()
+    }
+}
+
+fn parse_calories(line_number: usize, line: &str) -> Result<u64, InventoryError> {
+    line.parse().map_err(|_| InventoryError::InvalidNumber {
+        line: line_number,
+        text: line.to_string(),
+    })
+}
+
+fn add(total: u64, calories: u64, line_number: usize) -> Result<u64, InventoryError> {
+    total
+        .checked_add(calories)
+        .ok_or(InventoryError::Overflow { line: line_number })
+}
+
+/**
+ * Each elf's items, as they're completed. Elves are separated by blank lines, and a run of
+ * several only separates two elves, so there are never any with nothing at all
+ */
+fn inventories<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+) -> impl Iterator<Item = Result<Vec<u64>, InventoryError>> {
+    let mut lines = lines.into_iter().enumerate();
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+
+        let mut items = vec![];
+        let mut total: u64 = 0;
+
+        loop {
+            let Some((i, line)) = lines.next() else {
+                done = true;
+                return (!items.is_empty()).then_some(Ok(items));
+            };
+            let line = line.as_ref();
+
+            if line.is_empty() {
+                if items.is_empty() {
+                    continue;
+                }
+                return Some(Ok(items));
+            }
+
+            match parse_calories(i + 1, line).and_then(|calories| {
+                total = add(total, calories, i + 1)?;
+                Ok(calories)
+            }) {
+                Ok(calories) => items.push(calories),
+                Err(err) => {
+                    done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    })
+}
+
+/**
+ * Max and top `k` totals over any number of lines, only holding one elf's items at a time.
+ * Blank lines only separate elves, however many there are, so none of them is empty
+ */
+pub fn aggregate<S: AsRef<str>>(
+    lines: impl IntoIterator<Item = S>,
+    k: usize,
+) -> Result<Aggregate, InventoryError> {
+    let mut top = TopK::new(k);
+    let mut elves = 0;
+    let mut max = 0;
+
+    for (index, items) in inventories(lines).enumerate() {
+        // Can't overflow, `inventories` checks it
+        let total = items?.iter().sum();
+
+        elves += 1;
+        max = max.max(total);
+        top.push(total, index);
+    }
+
+    event!(Level::Debug, "aggregated", elves = elves, max = max);
+
+    Ok(Aggregate {
+        elves,
+        max,
+        top: top.into_sorted(),
+    })
+}
+
+/** Statistics over every elf's total calories */
+pub struct Summary {
+    pub count: usize,
+    pub total: u64,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub median: f64,
+    pub std_dev: f64,
+    /** Elves per equal width bucket, spanning `min..=max` */
+    pub histogram: Vec<usize>,
+}
 
-    let mut sum = 0;
+impl Summary {
+    const BUCKETS: usize = 10;
 
-    for line in input.iter() {
-        if line.is_empty() {
-            insert(sum);
-            sum = 0;
+    /** `None` if there are no elves */
+    pub fn new(elves: &[Elf]) -> Result<Option<Summary>, InventoryError> {
+        let mut totals: Vec<u64> = elves.iter().map(|elf| elf.total()).collect();
+        totals.sort_unstable();
+
+        let count = totals.len();
+        let (Some(min), Some(max)) = (totals.first().copied(), totals.last().copied()) else {
+            return Ok(None);
+        };
+        let total = sum(&totals)?;
+        let mean = total as f64 / count as f64;
+        let median = if count.is_multiple_of(2) {
+            (totals[count / 2 - 1] as f64 + totals[count / 2] as f64) / 2.0
         } else {
-            sum += line.parse::<i32>().unwrap();
+            totals[count / 2] as f64
+        };
+        let variance = totals
+            .iter()
+            .map(|it| (*it as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count as f64;
+
+        let mut summary = Summary {
+            count,
+            total,
+            min,
+            max,
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+            histogram: vec![0; Self::BUCKETS],
+        };
+
+        for value in totals {
+            let bucket = summary.bucket(value);
+            summary.histogram[bucket] += 1;
         }
+
+        Ok(Some(summary))
+    }
+
+    fn bucket_width(&self) -> u64 {
+        ((self.max - self.min) / Self::BUCKETS as u64) + 1
     }
 
-    insert(sum);
+    fn bucket(&self, value: u64) -> usize {
+        ((value - self.min) / self.bucket_width()) as usize
+    }
+
+    /** Inclusive range of totals that land in `bucket` */
+    pub fn bucket_range(&self, bucket: usize) -> (u64, u64) {
+        let from = self.min + bucket as u64 * self.bucket_width();
+
+        (from, from + self.bucket_width() - 1)
+    }
+}
+
+/** Elves are separated by blank lines, however many there are, so none of them is empty */
+pub fn parse_input(input: &[String]) -> Result<Vec<Elf>, InventoryError> {
+    let elves = inventories(input)
+        .enumerate()
+        .map(|(index, items)| items.map(|items| Elf { index, items }))
+        .collect::<Result<Vec<Elf>, _>>()?;
+
+    event!(Level::Debug, "parsed", elves = elves.len());
+
+    Ok(elves)
+}
+
+/** The `k` elves carrying the most calories, most first. Ties go to whoever came first */
+pub fn top(elves: &[Elf], k: usize) -> Vec<&Elf> {
+    let mut ranked: Vec<&Elf> = elves.iter().collect();
+    ranked.sort_by_key(|elf| (Reverse(elf.total()), elf.index));
+    ranked.truncate(k);
+
+    ranked
+}
+
+pub fn part1(input: &[String]) -> Result<u64, InventoryError> {
+    Ok(aggregate(input, 1)?.max)
+}
+
+fn sum(totals: &[u64]) -> Result<u64, InventoryError> {
+    totals.iter().try_fold(0u64, |sum, total| {
+        sum.checked_add(*total).ok_or(InventoryError::SumOverflow)
+    })
+}
+
+pub fn part2(input: &[String]) -> Result<u64, InventoryError> {
+    let top: Vec<u64> = aggregate(input, 3)?
+        .top
+        .iter()
+        .map(|(total, _)| *total)
+        .collect();
 
-    top3.iter().sum()
+    sum(&top)
 }
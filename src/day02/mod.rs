@@ -1,126 +1,720 @@
+use std::{collections::HashMap, fs};
+
+use crate::{event, trace::Level};
+
+use self::tournament::{Fixed, Frequency, Pattern, Player, Random, Scripted};
+
+pub mod tournament;
+
 pub fn run(input: Vec<String>) {
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    match part1(&input) {
+        Ok(answer) => println!("Part 1: {}", answer),
+        Err(err) => eprintln!("Part 1: {}", err),
+    }
+
+    match part2(&input) {
+        Ok(answer) => println!("Part 2: {}", answer),
+        Err(err) => eprintln!("Part 2: {}", err),
+    }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
+/** Score the strategy guide under a built in (`standard`, `rpsls`) or file defined set of rules */
+pub fn run_with_rules(input: Vec<String>, rules: &str) -> Result<(), String> {
+    let rules = load_rules(rules)?;
+
+    println!("Part 1: {}", score_shapes(&rules, &input)?);
+    match score_outcomes(&rules, &input) {
+        Ok(score) => println!("Part 2: {}", score),
+        Err(err) => eprintln!("Part 2: {}", err),
+    }
+
+    Ok(())
 }
 
-enum Outcome {
+/** Score every way of reading the guide's second column, best first */
+pub fn decode(input: Vec<String>, rules: &str) -> Result<(), String> {
+    let rules = load_rules(rules)?;
+    let decodings = decodings(&rules, &input)?;
+
+    for (i, decoding) in decodings.iter().enumerate() {
+        let mapping: Vec<String> = match &decoding.meaning {
+            Meaning::Shapes(shapes) => shapes
+                .iter()
+                .map(|(code, shape)| format!("{}={}", code, rules.name(*shape)))
+                .collect(),
+            Meaning::Outcomes(outcomes) => outcomes
+                .iter()
+                .map(|(code, outcome)| format!("{}={:?}", code, outcome))
+                .collect(),
+        };
+        let label = if i == 0 {
+            " (best)"
+        } else if i == decodings.len() - 1 {
+            " (worst)"
+        } else {
+            ""
+        };
+
+        println!("{:>8}  {}{}", decoding.score, mapping.join(" "), label);
+    }
+
+    let codes = second_column(&input).len();
+    if codes > OUTCOMES.len() {
+        println!(
+            "Outcome readings left out, the guide uses {} codes but there are only {} outcomes",
+            codes,
+            OUTCOMES.len()
+        );
+    }
+
+    Ok(())
+}
+
+/** Pit the built in strategies, and the guide's opponent column, against each other */
+pub fn tournament(input: Vec<String>, rules: &str, rounds: usize, seed: u64) -> Result<(), String> {
+    let rules = load_rules(rules)?;
+    let script: Vec<Shape> = guide(&input)
+        .map(|(number, line)| {
+            let (opponent, _) = split_line(line).map_err(|err| at(number, err))?;
+
+            rules
+                .opponent_shape(opponent)
+                .ok_or_else(|| at(number, format!("'{}' isn't an opponent code", opponent)))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let mut players: Vec<Box<dyn Player>> = rules
+        .shapes()
+        .map(|shape| Box::new(Fixed(shape)) as Box<dyn Player>)
+        .collect();
+    players.push(Box::new(Random::new(seed)));
+    players.push(Box::new(Frequency::default()));
+    players.push(Box::new(Pattern::new(3)));
+    if !script.is_empty() {
+        players.push(Box::new(Scripted::new("Guide", script)));
+    }
+
+    let standings = tournament::round_robin(&rules, &mut players, rounds);
+    event!(
+        Level::Debug,
+        "played",
+        players = players.len(),
+        rounds = rounds
+    );
+
+    println!(
+        "{:>2}  {:<20} {:>9} {:>7} {:>7} {:>7} {:>6}",
+        "#", "Player", "Score", "Won", "Drawn", "Lost", "Win%"
+    );
+    for (i, standing) in standings.iter().enumerate() {
+        println!(
+            "{:>2}  {:<20} {:>9} {:>7} {:>7} {:>7} {:>5.1}%",
+            i + 1,
+            standing.name,
+            standing.score,
+            standing.wins,
+            standing.draws,
+            standing.losses,
+            standing.win_rate() * 100.0
+        );
+    }
+
+    Ok(())
+}
+
+fn load_rules(rules: &str) -> Result<Rules, String> {
+    match rules {
+        "standard" => Ok(Rules::standard()),
+        "rpsls" => Rules::parse(RPSLS),
+        path => Rules::parse(&fs::read_to_string(path).map_err(|err| err.to_string())?),
+    }
+}
+
+/** Rock Paper Scissors */
+const STANDARD: &str = "
+shape Rock 1 A X
+shape Paper 2 B Y
+shape Scissors 3 C Z
+outcome lose 0 X
+outcome draw 3 Y
+outcome win 6 Z
+";
+
+/**
+ * Rock Paper Scissors Lizard Spock. Ordered so every shape beats the two before it,
+ * the guide's second column uses `V`-`Z` for shapes, but `X`-`Z` for outcomes. So reading it
+ * as outcomes only works for guides that never use `V` or `W`
+ */
+const RPSLS: &str = "
+shape Rock 1 A V
+shape Spock 2 B W
+shape Paper 3 C X
+shape Lizard 4 D Y
+shape Scissors 5 E Z
+outcome lose 0 X
+outcome draw 3 Y
+outcome win 6 Z
+";
+
+/** Index of a shape in its `Rules` */
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Shape(pub usize);
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Outcome {
     Win,
     Lose,
     Tie,
 }
 
-fn get_losing_play(shape: Shape) -> Shape {
-    match shape {
-        Shape::Rock => Shape::Scissors,
-        Shape::Paper => Shape::Rock,
-        Shape::Scissors => Shape::Paper,
-    }
+const OUTCOMES: [Outcome; 3] = [Outcome::Lose, Outcome::Tie, Outcome::Win];
+
+/** What the guide's second column is taken to mean, code by code */
+#[derive(Debug)]
+pub enum Meaning {
+    /** The shape each code stands for */
+    Shapes(Vec<(String, Shape)>),
+    /** The outcome each code stands for */
+    Outcomes(Vec<(String, Outcome)>),
 }
 
-fn get_winning_play(shape: Shape) -> Shape {
-    match shape {
-        Shape::Rock => Shape::Paper,
-        Shape::Paper => Shape::Scissors,
-        Shape::Scissors => Shape::Rock,
-    }
+#[derive(Debug)]
+pub struct Decoding {
+    pub meaning: Meaning,
+    pub score: u32,
 }
 
-fn get_shape_score(shape: Shape) -> u32 {
-    match shape {
-        Shape::Rock => 1,
-        Shape::Paper => 2,
-        Shape::Scissors => 3,
-    }
+#[derive(Debug)]
+struct ShapeRule {
+    name: String,
+    score: u32,
+    opponent_code: String,
+    my_code: String,
 }
 
-fn get_opponent_shape(opponent_play: &str) -> Shape {
-    match opponent_play {
-        "A" => Shape::Rock,
-        "B" => Shape::Paper,
-        "C" => Shape::Scissors,
-        _ => panic!("Invalid play!"),
-    }
+/**
+ * A cyclic hand game, loaded from a rules file of one directive per line (`#` starts a comment):
+ *
+ * - `shape <name> <score> <opponent code> <my code>`
+ * - `outcome <lose|draw|win> <score> <code>`
+ * - `beats <name> <name>...`, optional. Without any, each of an odd number of shapes beats
+ *   the half of the others that come just before it, wrapping around
+ */
+#[derive(Debug)]
+pub struct Rules {
+    shapes: Vec<ShapeRule>,
+    /** `beats[a][b]` is set when shape `a` beats shape `b` */
+    beats: Vec<Vec<bool>>,
+    outcome_scores: HashMap<Outcome, u32>,
+    outcome_codes: HashMap<String, Outcome>,
 }
 
-fn get_my_shape(my_play: &str) -> Shape {
-    match my_play {
-        "X" => Shape::Rock,
-        "Y" => Shape::Paper,
-        "Z" => Shape::Scissors,
-        _ => panic!("Invalid play!"),
+impl Rules {
+    pub fn standard() -> Rules {
+        Rules::parse(STANDARD).unwrap()
     }
-}
 
-fn get_desired_outcome(input: &str) -> Outcome {
-    match input {
-        "X" => Outcome::Lose,
-        "Y" => Outcome::Tie,
-        "Z" => Outcome::Win,
-        _ => panic!("Invalid input!"),
+    pub fn parse(text: &str) -> Result<Rules, String> {
+        let mut shapes: Vec<ShapeRule> = vec![];
+        let mut explicit_beats = vec![];
+        let mut outcome_scores = HashMap::new();
+        let mut outcome_codes = HashMap::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| format!("Rules line {}: {}", i + 1, message);
+            let line = line.split('#').next().unwrap().trim();
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words.as_slice() {
+                [] => {}
+                ["shape", name, score, opponent_code, my_code] => {
+                    for shape in &shapes {
+                        if shape.name == *name {
+                            return Err(error(&format!("Shape '{}' is already defined", name)));
+                        }
+                        if shape.opponent_code == *opponent_code || shape.my_code == *my_code {
+                            return Err(error("Each shape needs codes of its own"));
+                        }
+                    }
+
+                    shapes.push(ShapeRule {
+                        name: name.to_string(),
+                        score: score.parse().map_err(|_| error("Invalid score"))?,
+                        opponent_code: opponent_code.to_string(),
+                        my_code: my_code.to_string(),
+                    });
+                }
+                ["outcome", outcome, score, code] => {
+                    let outcome = match *outcome {
+                        "lose" => Outcome::Lose,
+                        "draw" => Outcome::Tie,
+                        "win" => Outcome::Win,
+                        _ => return Err(error("Outcome must be lose, draw or win")),
+                    };
+                    let score = score.parse().map_err(|_| error("Invalid score"))?;
+
+                    // One code per outcome, so a code always means the same thing
+                    if outcome_scores.insert(outcome, score).is_some() {
+                        return Err(error(&format!("{:?} is already defined", outcome)));
+                    }
+                    if outcome_codes.insert(code.to_string(), outcome).is_some() {
+                        return Err(error(&format!("Code '{}' is already an outcome", code)));
+                    }
+                }
+                ["beats", winner, losers @ ..] => {
+                    for loser in losers {
+                        explicit_beats.push((i + 1, winner.to_string(), loser.to_string()));
+                    }
+                }
+                _ => return Err(error(&format!("Unrecognised directive '{}'", line))),
+            }
+        }
+
+        let count = shapes.len();
+        if count == 0 {
+            return Err(String::from("Rules must define at least one shape"));
+        }
+
+        for outcome in [Outcome::Lose, Outcome::Tie, Outcome::Win] {
+            if !outcome_scores.contains_key(&outcome) {
+                return Err(format!("Rules are missing a score for {:?}", outcome));
+            }
+        }
+
+        let mut beats = vec![vec![false; count]; count];
+
+        if explicit_beats.is_empty() {
+            if count.is_multiple_of(2) {
+                return Err(String::from(
+                    "Cyclic rules need an odd number of shapes, or explicit 'beats' lines",
+                ));
+            }
+
+            for (winner, row) in beats.iter_mut().enumerate() {
+                for offset in 1..=count / 2 {
+                    row[(winner + count - offset) % count] = true;
+                }
+            }
+        } else {
+            let find = |line: usize, name: &str| {
+                shapes
+                    .iter()
+                    .position(|shape| shape.name == name)
+                    .ok_or_else(|| format!("Rules line {}: Unknown shape '{}'", line, name))
+            };
+
+            for (line, winner, loser) in explicit_beats {
+                let (winner, loser) = (find(line, &winner)?, find(line, &loser)?);
+
+                if winner == loser || beats[loser][winner] {
+                    return Err(format!("Rules line {}: Contradictory 'beats'", line));
+                }
+
+                beats[winner][loser] = true;
+            }
+        }
+
+        Ok(Rules {
+            shapes,
+            beats,
+            outcome_scores,
+            outcome_codes,
+        })
+    }
+
+    pub fn name(&self, shape: Shape) -> &str {
+        &self.shapes[shape.0].name
+    }
+
+    pub fn shapes(&self) -> impl Iterator<Item = Shape> {
+        (0..self.shapes.len()).map(Shape)
+    }
+
+    /** How `mine` fares against `opponent` */
+    pub fn outcome(&self, opponent: Shape, mine: Shape) -> Outcome {
+        if self.beats[mine.0][opponent.0] {
+            Outcome::Win
+        } else if self.beats[opponent.0][mine.0] {
+            Outcome::Lose
+        } else {
+            Outcome::Tie
+        }
+    }
+
+    /** The first shape (in rule order) that gets `outcome` against `opponent` */
+    pub fn shape_for(&self, opponent: Shape, outcome: Outcome) -> Option<Shape> {
+        self.shapes()
+            .find(|mine| self.outcome(opponent, *mine) == outcome)
+    }
+
+    fn opponent_shape(&self, code: &str) -> Option<Shape> {
+        self.shapes
+            .iter()
+            .position(|shape| shape.opponent_code == code)
+            .map(Shape)
+    }
+
+    fn my_shape(&self, code: &str) -> Option<Shape> {
+        self.shapes
+            .iter()
+            .position(|shape| shape.my_code == code)
+            .map(Shape)
+    }
+
+    fn desired_outcome(&self, code: &str) -> Option<Outcome> {
+        self.outcome_codes.get(code).copied()
     }
 }
 
-fn play(opponent_shape: Shape, my_shape: Shape) -> u32 {
-    let my_shape_val = get_shape_score(my_shape);
-    let result = if get_losing_play(my_shape) == opponent_shape {
-        6 // We win
-    } else if get_losing_play(opponent_shape) == my_shape {
-        0 // We lose
-    } else {
-        3 // Tie
-    };
+pub fn play(rules: &Rules, opponent_shape: Shape, my_shape: Shape) -> u32 {
+    let my_shape_val = rules.shapes[my_shape.0].score;
+    let result = rules.outcome_scores[&rules.outcome(opponent_shape, my_shape)];
 
     result + my_shape_val
 }
 
-fn parse_input_line_v1(line: &str) -> (Shape, Shape) {
-    let split: [&str; 2] = line.split(' ').collect::<Vec<&str>>().try_into().unwrap();
+fn split_line(line: &str) -> Result<(&str, &str), String> {
+    let mut words = line.split_whitespace();
 
-    (get_opponent_shape(split[0]), get_my_shape(split[1]))
+    match (words.next(), words.next(), words.next()) {
+        (Some(opponent), Some(mine), None) => Ok((opponent, mine)),
+        _ => Err(format!("'{}' isn't two codes", line)),
+    }
+}
+
+/** The guide's non-blank lines, numbered from 1 */
+fn guide(input: &[String]) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, line.as_str()))
+}
+
+fn at(line: usize, message: String) -> String {
+    format!("Line {}: {}", line, message)
 }
 
-fn parse_input_line_v2(line: &str) -> (Shape, Outcome) {
-    let split: [&str; 2] = line.split(' ').collect::<Vec<&str>>().try_into().unwrap();
+pub fn parse_input_line_v1(rules: &Rules, line: &str) -> Result<(Shape, Shape), String> {
+    let (opponent, mine) = split_line(line)?;
 
-    (get_opponent_shape(split[0]), get_desired_outcome(split[1]))
+    Ok((
+        rules
+            .opponent_shape(opponent)
+            .ok_or_else(|| format!("'{}' isn't an opponent code", opponent))?,
+        rules
+            .my_shape(mine)
+            .ok_or_else(|| format!("'{}' isn't a shape code", mine))?,
+    ))
 }
 
-fn part1(input: &Vec<String>) -> u32 {
+pub fn parse_input_line_v2(rules: &Rules, line: &str) -> Result<(Shape, Outcome), String> {
+    let (opponent, outcome) = split_line(line)?;
+
+    Ok((
+        rules
+            .opponent_shape(opponent)
+            .ok_or_else(|| format!("'{}' isn't an opponent code", opponent))?,
+        rules
+            .desired_outcome(outcome)
+            .ok_or_else(|| format!("'{}' isn't an outcome code", outcome))?,
+    ))
+}
+
+/** Second column is the shape to play. An error naming the first line that doesn't fit */
+pub fn score_shapes(rules: &Rules, input: &[String]) -> Result<u32, String> {
     let mut score = 0;
 
-    for line in input {
-        let (opponent_play, my_play) = parse_input_line_v1(line);
-        score += play(opponent_play, my_play);
+    for (number, line) in guide(input) {
+        let (opponent_play, my_play) =
+            parse_input_line_v1(rules, line).map_err(|err| at(number, err))?;
+        score += play(rules, opponent_play, my_play);
     }
 
-    score
+    Ok(score)
+}
+
+/**
+ * The shape that gets `outcome` against `opponent`. Explicit `beats` can leave a shape that
+ * nothing beats, or that beats nothing, so there might not be one
+ */
+fn shape_for_outcome(rules: &Rules, opponent: Shape, outcome: Outcome) -> Result<Shape, String> {
+    rules.shape_for(opponent, outcome).ok_or_else(|| {
+        format!(
+            "No shape gets {:?} against {}",
+            outcome,
+            rules.name(opponent)
+        )
+    })
 }
 
-fn part2(input: &Vec<String>) -> u32 {
+/** Second column is the outcome to aim for. An error naming the first line that doesn't fit */
+pub fn score_outcomes(rules: &Rules, input: &[String]) -> Result<u32, String> {
     let mut score = 0;
 
-    for line in input {
-        let (opponent_play, desired_outcome) = parse_input_line_v2(line);
+    for (number, line) in guide(input) {
+        let (opponent_play, desired_outcome) =
+            parse_input_line_v2(rules, line).map_err(|err| at(number, err))?;
+        let my_play = shape_for_outcome(rules, opponent_play, desired_outcome)
+            .map_err(|err| at(number, err))?;
 
-        match desired_outcome {
-            Outcome::Lose => {
-                let my_play = get_losing_play(opponent_play);
-                score += play(opponent_play, my_play);
-            }
-            Outcome::Win => {
-                let my_play = get_winning_play(opponent_play);
-                score += play(opponent_play, my_play);
+        score += play(rules, opponent_play, my_play);
+    }
+
+    Ok(score)
+}
+
+/** The distinct codes in the guide's second column, sorted */
+fn second_column(input: &[String]) -> Vec<&str> {
+    let mut codes: Vec<&str> = guide(input)
+        .filter_map(|(_, line)| Some(split_line(line).ok()?.1))
+        .collect();
+    codes.sort_unstable();
+    codes.dedup();
+
+    codes
+}
+
+/**
+ * Every way of picking `k` distinct indices below `n`, in order, made one at a time.
+ * Nothing when `k > n`
+ */
+struct Arrangements {
+    n: usize,
+    next: Option<Vec<usize>>,
+}
+
+impl Arrangements {
+    fn new(n: usize, k: usize) -> Arrangements {
+        Arrangements {
+            n,
+            next: (k <= n).then(|| (0..k).collect()),
+        }
+    }
+}
+
+impl Iterator for Arrangements {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let current = self.next.take()?;
+        let mut following = current.clone();
+
+        // Bump the rightmost index that can be, then fill in after it with the smallest unused
+        for i in (0..following.len()).rev() {
+            let used = &following[..i];
+            let Some(bumped) = (following[i] + 1..self.n).find(|it| !used.contains(it)) else {
+                continue;
+            };
+
+            following[i] = bumped;
+            for j in i + 1..following.len() {
+                let used = &following[..j];
+                following[j] = (0..self.n).find(|it| !used.contains(it)).unwrap();
             }
-            Outcome::Tie => score += play(opponent_play, opponent_play),
+
+            self.next = Some(following);
+            break;
+        }
+
+        Some(current)
+    }
+}
+
+/**
+ * Score the guide under every way of giving its second column's codes distinct shapes, then
+ * distinct outcomes. The codes needn't be the rules' own. Rounds are tallied up front, so each
+ * reading only costs one pass over the distinct pairs, and readings are scored as they're made
+ */
+pub fn decodings(rules: &Rules, input: &[String]) -> Result<Vec<Decoding>, String> {
+    let codes = second_column(input);
+    let mut rounds = HashMap::<(usize, usize), u32>::new();
+
+    for (number, line) in guide(input) {
+        let (opponent, code) = split_line(line).map_err(|err| at(number, err))?;
+        let opponent = rules
+            .opponent_shape(opponent)
+            .ok_or_else(|| at(number, format!("'{}' isn't an opponent code", opponent)))?;
+        let code = codes.binary_search(&code).unwrap();
+
+        *rounds.entry((opponent.0, code)).or_insert(0) += 1;
+    }
+
+    let shapes: Vec<Shape> = rules.shapes().collect();
+    let mut result = vec![];
+
+    for picked in Arrangements::new(shapes.len(), codes.len()) {
+        let score = rounds
+            .iter()
+            .map(|((opponent, code), count)| {
+                count * play(rules, Shape(*opponent), shapes[picked[*code]])
+            })
+            .sum();
+
+        result.push(Decoding {
+            meaning: Meaning::Shapes(
+                codes
+                    .iter()
+                    .zip(&picked)
+                    .map(|(code, shape)| (code.to_string(), shapes[*shape]))
+                    .collect(),
+            ),
+            score,
+        });
+    }
+
+    for picked in Arrangements::new(OUTCOMES.len(), codes.len()) {
+        // Readings that ask for an outcome no shape can get are left out
+        let Some(score) = rounds
+            .iter()
+            .map(|((opponent, code), count)| {
+                let my_play = rules.shape_for(Shape(*opponent), OUTCOMES[picked[*code]])?;
+
+                Some(count * play(rules, Shape(*opponent), my_play))
+            })
+            .sum::<Option<u32>>()
+        else {
+            continue;
         };
+
+        result.push(Decoding {
+            meaning: Meaning::Outcomes(
+                codes
+                    .iter()
+                    .zip(&picked)
+                    .map(|(code, outcome)| (code.to_string(), OUTCOMES[*outcome]))
+                    .collect(),
+            ),
+            score,
+        });
+    }
+
+    result.sort_by_key(|decoding| std::cmp::Reverse(decoding.score));
+    event!(Level::Debug, "decoded", decodings = result.len());
+
+    Ok(result)
+}
+
+pub fn part1(input: &[String]) -> Result<u32, String> {
+    score_shapes(&Rules::standard(), input)
+}
+
+pub fn part2(input: &[String]) -> Result<u32, String> {
+    score_outcomes(&Rules::standard(), input)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{
+        decodings, part1, part2, score_outcomes, score_shapes, Arrangements, Meaning, Outcome,
+        Rules, Shape, RPSLS,
+    };
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn example() {
+        let input = lines("A Y\nB X\nC Z\n");
+
+        assert_eq!(part1(&input), Ok(15));
+        assert_eq!(part2(&input), Ok(12));
+    }
+
+    #[test]
+    fn unknown_codes_name_the_line() {
+        let input = lines("A Y\n\nB Q\n");
+
+        assert_eq!(
+            part1(&input),
+            Err(String::from("Line 3: 'Q' isn't a shape code"))
+        );
+        assert_eq!(
+            part2(&input),
+            Err(String::from("Line 3: 'Q' isn't an outcome code"))
+        );
+        assert!(part1(&lines("A Y Z\n")).is_err());
+    }
+
+    #[test]
+    fn duplicate_rules_are_rejected() {
+        let outcomes = "outcome lose 0 X\noutcome draw 3 Y\noutcome win 6 Z\n";
+
+        for shapes in [
+            "shape Rock 1 A X\nshape Rock 2 B Y\nshape Paper 3 C Z\n",
+            "shape Rock 1 A X\nshape Paper 2 A Y\nshape Scissors 3 C Z\n",
+            "shape Rock 1 A X\nshape Paper 2 B X\nshape Scissors 3 C Z\n",
+        ] {
+            assert!(Rules::parse(&format!("{}{}", shapes, outcomes)).is_err());
+        }
+
+        let shapes = "shape Rock 1 A X\nshape Paper 2 B Y\nshape Scissors 3 C Z\n";
+        for outcomes in [
+            "outcome lose 0 X\noutcome lose 0 W\noutcome draw 3 Y\noutcome win 6 Z\n",
+            "outcome lose 0 X\noutcome draw 3 X\noutcome win 6 Z\n",
+        ] {
+            assert!(Rules::parse(&format!("{}{}", shapes, outcomes)).is_err());
+        }
+    }
+
+    #[test]
+    fn outcome_no_shape_gets_is_an_error() {
+        // Nothing beats Rock
+        let rules = Rules::parse(
+            "shape Rock 1 A X\nshape Paper 2 B Y\nbeats Rock Paper\n\
+             outcome lose 0 X\noutcome draw 3 Y\noutcome win 6 Z\n",
+        )
+        .unwrap();
+
+        assert_eq!(score_shapes(&rules, &lines("B X\nA Y\n")), Ok(7 + 2));
+        assert_eq!(
+            score_outcomes(&rules, &lines("A Y\nA Z\n")),
+            Err(String::from("Line 2: No shape gets Win against Rock"))
+        );
+    }
+
+    #[test]
+    fn arrangements_are_distinct_and_complete() {
+        let arrangements: Vec<Vec<usize>> = Arrangements::new(5, 3).collect();
+        let unique: HashSet<&Vec<usize>> = arrangements.iter().collect();
+
+        assert_eq!(arrangements.len(), 5 * 4 * 3);
+        assert_eq!(unique.len(), arrangements.len());
+        assert!(arrangements
+            .iter()
+            .all(|it| it.iter().all(|i| *i < 5) && it.iter().collect::<HashSet<_>>().len() == 3));
+        assert_eq!(Arrangements::new(3, 0).count(), 1);
+        assert_eq!(Arrangements::new(3, 4).count(), 0);
     }
 
-    score
+    #[test]
+    fn decodings_cover_only_the_codes_used() {
+        let input = lines("A Y\nB X\nC Z\n");
+        let standard = decodings(&Rules::standard(), &input).unwrap();
+
+        assert_eq!(standard.len(), 6 + 6);
+        assert!(standard.iter().any(|it| it.score == 15
+            && matches!(&it.meaning, Meaning::Shapes(shapes)
+                if shapes.iter().map(|(_, shape)| *shape).eq([Shape(0), Shape(1), Shape(2)]))));
+        assert!(standard.iter().any(|it| it.score == 12
+            && matches!(&it.meaning, Meaning::Outcomes(outcomes)
+                if outcomes.iter().map(|(_, outcome)| *outcome)
+                    .eq([Outcome::Lose, Outcome::Tie, Outcome::Win]))));
+
+        // Five shapes, but only three codes in the guide
+        let rpsls = decodings(&Rules::parse(RPSLS).unwrap(), &input).unwrap();
+        assert_eq!(rpsls.len(), 5 * 4 * 3 + 6);
+
+        // Codes outside the rules are fine in the second column, but not the first
+        assert_eq!(
+            decodings(&Rules::standard(), &lines("A Q\n"))
+                .unwrap()
+                .len(),
+            3 + 3
+        );
+        assert!(decodings(&Rules::standard(), &lines("Q X\n")).is_err());
+    }
 }
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use super::{play, Outcome, Rules, Shape};
+
+/** A strategy that picks a shape each round, and can learn from what its opponent played */
+pub trait Player {
+    fn name(&self, rules: &Rules) -> String;
+
+    fn choose(&mut self, rules: &Rules) -> Shape;
+
+    fn observe(&mut self, _mine: Shape, _theirs: Shape) {}
+
+    /** Forget everything learned, ready for a new opponent */
+    fn reset(&mut self) {}
+}
+
+/** Small, seedable PRNG (SplitMix64), so tournaments are repeatable */
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+/** The shape that beats `shape` */
+fn counter(rules: &Rules, shape: Shape) -> Shape {
+    rules.shape_for(shape, Outcome::Win).unwrap_or(shape)
+}
+
+/** Always plays the same shape */
+pub struct Fixed(pub Shape);
+
+impl Player for Fixed {
+    fn name(&self, rules: &Rules) -> String {
+        format!("Always {}", rules.name(self.0))
+    }
+
+    fn choose(&mut self, _rules: &Rules) -> Shape {
+        self.0
+    }
+}
+
+/** Uniformly random shapes */
+pub struct Random {
+    seed: u64,
+    rng: Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Player for Random {
+    fn name(&self, _rules: &Rules) -> String {
+        format!("Random (seed {})", self.seed)
+    }
+
+    fn choose(&mut self, rules: &Rules) -> Shape {
+        Shape(self.rng.below(rules.shapes().count()))
+    }
+
+    fn reset(&mut self) {
+        self.rng = Rng::new(self.seed);
+    }
+}
+
+/** Counters whatever the opponent has played most often */
+#[derive(Default)]
+pub struct Frequency {
+    counts: HashMap<usize, usize>,
+}
+
+impl Player for Frequency {
+    fn name(&self, _rules: &Rules) -> String {
+        String::from("Frequency")
+    }
+
+    fn choose(&mut self, rules: &Rules) -> Shape {
+        let favourite = rules
+            .shapes()
+            .max_by_key(|shape| {
+                (
+                    self.counts.get(&shape.0).copied(),
+                    std::cmp::Reverse(shape.0),
+                )
+            })
+            .unwrap();
+
+        counter(rules, favourite)
+    }
+
+    fn observe(&mut self, _mine: Shape, theirs: Shape) {
+        *self.counts.entry(theirs.0).or_insert(0) += 1;
+    }
+
+    fn reset(&mut self) {
+        self.counts.clear();
+    }
+}
+
+/**
+ * Looks for the longest recent run of the opponent's moves (up to `depth`) that has happened
+ * before, and counters whatever they most often played next. Falls back to `Frequency`
+ */
+pub struct Pattern {
+    depth: usize,
+    history: Vec<usize>,
+    /** What followed each run of moves seen so far, and how often */
+    followers: HashMap<Vec<usize>, HashMap<usize, usize>>,
+    fallback: Frequency,
+}
+
+impl Pattern {
+    pub fn new(depth: usize) -> Pattern {
+        Pattern {
+            depth,
+            history: vec![],
+            followers: HashMap::new(),
+            fallback: Frequency::default(),
+        }
+    }
+
+    fn predict(&self) -> Option<Shape> {
+        (1..=self.depth.min(self.history.len()))
+            .rev()
+            .find_map(|length| {
+                self.followers
+                    .get(&self.history[self.history.len() - length..])
+                    .and_then(|next| {
+                        next.iter()
+                            .max_by_key(|(shape, count)| (**count, std::cmp::Reverse(**shape)))
+                    })
+            })
+            .map(|(shape, _)| Shape(*shape))
+    }
+}
+
+impl Player for Pattern {
+    fn name(&self, _rules: &Rules) -> String {
+        format!("Pattern (depth {})", self.depth)
+    }
+
+    fn choose(&mut self, rules: &Rules) -> Shape {
+        match self.predict() {
+            Some(prediction) => counter(rules, prediction),
+            None => self.fallback.choose(rules),
+        }
+    }
+
+    fn observe(&mut self, mine: Shape, theirs: Shape) {
+        for length in 1..=self.depth.min(self.history.len()) {
+            let run = self.history[self.history.len() - length..].to_vec();
+            *self
+                .followers
+                .entry(run)
+                .or_default()
+                .entry(theirs.0)
+                .or_insert(0) += 1;
+        }
+
+        self.history.push(theirs.0);
+        self.fallback.observe(mine, theirs);
+    }
+
+    fn reset(&mut self) {
+        self.history.clear();
+        self.followers.clear();
+        self.fallback.reset();
+    }
+}
+
+/** Plays a fixed script, such as the guide's opponent column, on repeat */
+pub struct Scripted {
+    name: String,
+    script: Vec<Shape>,
+    position: usize,
+}
+
+impl Scripted {
+    pub fn new(name: &str, script: Vec<Shape>) -> Scripted {
+        Scripted {
+            name: name.to_string(),
+            script,
+            position: 0,
+        }
+    }
+}
+
+impl Player for Scripted {
+    fn name(&self, _rules: &Rules) -> String {
+        self.name.clone()
+    }
+
+    fn choose(&mut self, _rules: &Rules) -> Shape {
+        let shape = self.script[self.position % self.script.len()];
+        self.position += 1;
+
+        shape
+    }
+
+    fn reset(&mut self) {
+        self.position = 0;
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub score: u64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+}
+
+impl Standing {
+    pub fn win_rate(&self) -> f64 {
+        let played = self
+            .wins
+            .saturating_add(self.draws)
+            .saturating_add(self.losses);
+
+        if played == 0 {
+            0.0
+        } else {
+            self.wins as f64 / played as f64
+        }
+    }
+
+    fn record(&mut self, rules: &Rules, mine: Shape, theirs: Shape) {
+        // Long enough tournaments saturate rather than overflow
+        self.score = self
+            .score
+            .saturating_add(u64::from(play(rules, theirs, mine)));
+
+        let tally = match rules.outcome(theirs, mine) {
+            Outcome::Win => &mut self.wins,
+            Outcome::Tie => &mut self.draws,
+            Outcome::Lose => &mut self.losses,
+        };
+        *tally = tally.saturating_add(1);
+    }
+}
+
+/** Every player meets every other for `rounds` rounds. Returns the league table, best first */
+pub fn round_robin(rules: &Rules, players: &mut [Box<dyn Player>], rounds: usize) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = players
+        .iter()
+        .map(|player| Standing {
+            name: player.name(rules),
+            ..Default::default()
+        })
+        .collect();
+
+    for i in 0..players.len() {
+        for j in (i + 1)..players.len() {
+            let (left, right) = players.split_at_mut(j);
+            let (first, second) = (&mut left[i], &mut right[0]);
+            first.reset();
+            second.reset();
+
+            for _ in 0..rounds {
+                let first_shape = first.choose(rules);
+                let second_shape = second.choose(rules);
+
+                first.observe(first_shape, second_shape);
+                second.observe(second_shape, first_shape);
+                standings[i].record(rules, first_shape, second_shape);
+                standings[j].record(rules, second_shape, first_shape);
+            }
+        }
+    }
+
+    standings.sort_by_key(|standing| std::cmp::Reverse(standing.score));
+
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shape, Standing};
+    use crate::day02::Rules;
+
+    #[test]
+    fn standings_saturate() {
+        let mut standing = Standing {
+            score: u64::MAX - 1,
+            wins: u64::MAX,
+            ..Default::default()
+        };
+
+        // Paper beats Rock
+        standing.record(&Rules::standard(), Shape(1), Shape(0));
+
+        assert_eq!(standing.score, u64::MAX);
+        assert_eq!(standing.wins, u64::MAX);
+        assert_eq!(standing.win_rate(), 1.0);
+    }
+}
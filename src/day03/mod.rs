@@ -1,86 +1,363 @@
-use std::collections::HashSet;
+use std::{
+    fmt::{self, Display},
+    num::NonZeroUsize,
+};
+
+use crate::{event, trace::Level};
 
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
-fn build_compartment_set(compartment: &str) -> HashSet<char> {
-    let mut set = HashSet::new();
+/** Print the items each compartment split or line group has in common */
+pub fn shared_report(input: Vec<String>, grouping: Grouping) {
+    let mut total = 0;
+
+    for group in shared(&input, grouping) {
+        let items: Vec<String> = group
+            .items
+            .iter()
+            .map(|item| format!("{}({})", item, priority(item).unwrap()))
+            .collect();
 
-    for c in compartment.chars() {
-        set.insert(c);
+        println!("Line {:>4}: {}", group.line, items.join(" "));
+        total += group.items.priority();
     }
 
-    set
+    println!("Total: {}", total);
 }
 
-fn get_priority(item: char) -> u32 {
-    match item {
-        'a'..='z' => 1 + (item as u32) - ('a' as u32),
-        'A'..='Z' => 27 + (item as u32) - ('A' as u32),
-        _ => 0,
+/** Like `run`, but refuses to answer if `validate` finds anything wrong with the input */
+pub fn run_strict(input: Vec<String>) -> Result<(), String> {
+    let anomalies = validate(&input);
+
+    if let Some(first) = anomalies.first() {
+        return Err(format!(
+            "{} anomalies in the input, starting with {}",
+            anomalies.len(),
+            first
+        ));
     }
+
+    run(input);
+
+    Ok(())
 }
 
-fn part1(input: &Vec<String>) -> u32 {
-    let mut result = 0;
+/** List everything suspicious about the input, by line */
+pub fn validation_report(input: Vec<String>) {
+    let anomalies = validate(&input);
 
-    for line in input {
-        let mid = line.chars().count() / 2;
-        let compartment1 = &line[..mid];
-        let compartment2 = &line[mid..];
-        let compartment1_set = build_compartment_set(compartment1);
-        let mut shared_item = None;
+    for anomaly in &anomalies {
+        println!("{}", anomaly);
+    }
 
-        for c in compartment2.chars() {
-            if compartment1_set.contains(&c) {
-                shared_item = Some(c);
-                break;
-            }
-        }
+    println!("{} anomalies", anomalies.len());
+}
 
-        if let Some(shared_item) = shared_item {
-            let priority = get_priority(shared_item);
-            result += priority;
-        }
+/** The 52 item types, as a bit mask. `a`-`z` are bits 0-25, `A`-`Z` bits 26-51 */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct ItemSet(u64);
+
+impl ItemSet {
+    pub const ALL: ItemSet = ItemSet((1 << 52) - 1);
+
+    /** Anything that isn't an item type is left out */
+    pub fn from_items(items: &str) -> ItemSet {
+        ItemSet(
+            items
+                .chars()
+                .filter_map(priority)
+                .fold(0, |mask, priority| mask | 1 << (priority - 1)),
+        )
+    }
+
+    pub fn intersection(self, other: ItemSet) -> ItemSet {
+        ItemSet(self.0 & other.0)
+    }
+
+    pub fn len(self) -> u32 {
+        self.0.count_ones()
     }
 
-    result
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /** Sum of the priorities of every item in the set */
+    pub fn priority(self) -> u32 {
+        self.iter().filter_map(priority).sum()
+    }
+
+    /** Items in priority order */
+    pub fn iter(self) -> impl Iterator<Item = char> {
+        (1..=52)
+            .filter(move |priority| self.0 & 1 << (priority - 1) != 0)
+            .map(item)
+    }
 }
 
-fn part2(input: &Vec<String>) -> u32 {
-    let mut result = 0;
+impl Display for ItemSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.iter().collect::<String>())
+    }
+}
+
+/** How to find the sets of items that should have something in common. Never groups of none */
+#[derive(Clone, Copy, Debug)]
+pub enum Grouping {
+    /** Split each line into this many equal compartments */
+    Compartments(NonZeroUsize),
+    /** Take this many lines at a time. A short group at the end is left out */
+    Lines(NonZeroUsize),
+}
+
+impl Grouping {
+    /** Each rucksack's two compartments */
+    pub const HALVES: Grouping = Grouping::Compartments(NonZeroUsize::new(2).unwrap());
+    /** Each group of three elves */
+    pub const TRIOS: Grouping = Grouping::Lines(NonZeroUsize::new(3).unwrap());
+}
 
-    let mut i = 0;
-    while i <= input.len() - 3 {
-        let bag1 = &input[i];
-        let bag2 = &input[i + 1];
-        let bag3 = &input[i + 2];
-        let bag1_set = build_compartment_set(bag1);
-        let mut bag2_set = HashSet::new();
+/** Items shared by one compartment split or line group */
+#[derive(Debug)]
+pub struct Shared {
+    /** First line of the group, from 1 */
+    pub line: usize,
+    pub items: ItemSet,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AnomalyKind {
+    /** Not an item type, so it has no priority */
+    InvalidItem {
+        item: char,
+        column: usize,
+    },
+    /** The compartments can't be the same size */
+    OddLength(usize),
+    NoSharedItem,
+    SeveralSharedItems(ItemSet),
+    MissingBadge,
+    AmbiguousBadge(ItemSet),
+    /** The last group has fewer than three elves */
+    IncompleteGroup(usize),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Anomaly {
+    /** From 1. For group anomalies, the group's first line */
+    pub line: usize,
+    pub kind: AnomalyKind,
+}
 
-        for c in bag2.chars() {
-            if bag1_set.contains(&c) {
-                bag2_set.insert(c);
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+
+        match &self.kind {
+            AnomalyKind::InvalidItem { item, column } => {
+                write!(f, "{:?} at column {} isn't an item", item, column)
+            }
+            AnomalyKind::OddLength(length) => {
+                write!(f, "{} items can't be split into two compartments", length)
+            }
+            AnomalyKind::NoSharedItem => write!(f, "compartments have no item in common"),
+            AnomalyKind::SeveralSharedItems(items) => {
+                write!(f, "compartments have several items in common: {}", items)
+            }
+            AnomalyKind::MissingBadge => write!(f, "group has no badge"),
+            AnomalyKind::AmbiguousBadge(items) => {
+                write!(f, "group could have any of these badges: {}", items)
+            }
+            AnomalyKind::IncompleteGroup(size) => {
+                write!(f, "last group only has {} elves", size)
             }
         }
+    }
+}
+
+/** Check each rucksack, then each group of three, against what the puzzle promises */
+pub fn validate(input: &[String]) -> Vec<Anomaly> {
+    let mut anomalies = vec![];
 
-        let mut shared_item = None;
-        for c in bag3.chars() {
-            if bag2_set.contains(&c) {
-                shared_item = Some(c);
-                break;
+    for (i, line) in input.iter().enumerate() {
+        let mut report = |kind| anomalies.push(Anomaly { line: i + 1, kind });
+
+        for (column, item) in line.chars().enumerate() {
+            if priority(item).is_none() {
+                report(AnomalyKind::InvalidItem {
+                    item,
+                    column: column + 1,
+                });
             }
         }
 
-        if let Some(shared_item) = shared_item {
-            let priority = get_priority(shared_item);
-            result += priority;
+        let length = line.chars().count();
+        if !length.is_multiple_of(2) {
+            report(AnomalyKind::OddLength(length));
+        }
+    }
+
+    for group in shared(input, Grouping::HALVES) {
+        match group.items.len() {
+            0 => anomalies.push(Anomaly {
+                line: group.line,
+                kind: AnomalyKind::NoSharedItem,
+            }),
+            1 => {}
+            _ => anomalies.push(Anomaly {
+                line: group.line,
+                kind: AnomalyKind::SeveralSharedItems(group.items),
+            }),
+        }
+    }
+
+    for group in shared(input, Grouping::TRIOS) {
+        match group.items.len() {
+            0 => anomalies.push(Anomaly {
+                line: group.line,
+                kind: AnomalyKind::MissingBadge,
+            }),
+            1 => {}
+            _ => anomalies.push(Anomaly {
+                line: group.line,
+                kind: AnomalyKind::AmbiguousBadge(group.items),
+            }),
         }
+    }
+
+    let leftover = input.len() % 3;
+    if leftover != 0 {
+        anomalies.push(Anomaly {
+            line: input.len() - leftover + 1,
+            kind: AnomalyKind::IncompleteGroup(leftover),
+        });
+    }
+
+    // Stable, so anomalies on the same line keep the order they were found in
+    anomalies.sort_by_key(|anomaly| anomaly.line);
+    event!(Level::Debug, "validated", anomalies = anomalies.len());
+
+    anomalies
+}
+
+pub fn priority(item: char) -> Option<u32> {
+    match item {
+        'a'..='z' => Some(1 + (item as u32) - ('a' as u32)),
+        'A'..='Z' => Some(27 + (item as u32) - ('A' as u32)),
+        _ => None,
+    }
+}
+
+/** Inverse of `priority` */
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + (priority - 1) as u8) as char,
+        _ => (b'A' + (priority - 27) as u8) as char,
+    }
+}
+
+/**
+ * Split `line` into `n` compartments of the same number of items. When that doesn't divide
+ * evenly, earlier compartments are the shorter ones
+ */
+pub fn compartments(line: &str, n: usize) -> Vec<&str> {
+    let mut offsets: Vec<usize> = line.char_indices().map(|(offset, _)| offset).collect();
+    let length = offsets.len();
+    offsets.push(line.len());
+
+    (0..n)
+        .map(|i| &line[offsets[i * length / n]..offsets[(i + 1) * length / n]])
+        .collect()
+}
+
+/** Items common to every set, or none if there aren't any sets */
+pub fn common<'a>(sets: impl IntoIterator<Item = &'a str>) -> ItemSet {
+    let mut sets = sets.into_iter().map(ItemSet::from_items).peekable();
 
-        i += 3;
+    if sets.peek().is_none() {
+        return ItemSet::default();
     }
 
-    result
+    sets.fold(ItemSet::ALL, ItemSet::intersection)
+}
+
+pub fn shared(input: &[String], grouping: Grouping) -> Vec<Shared> {
+    let shared: Vec<Shared> = match grouping {
+        Grouping::Compartments(n) => input
+            .iter()
+            .enumerate()
+            .map(|(i, line)| Shared {
+                line: i + 1,
+                items: common(compartments(line, n.get())),
+            })
+            .collect(),
+        Grouping::Lines(n) => input
+            .chunks_exact(n.get())
+            .enumerate()
+            .map(|(i, group)| Shared {
+                line: i * n.get() + 1,
+                items: common(group.iter().map(String::as_str)),
+            })
+            .collect(),
+    };
+
+    event!(
+        Level::Debug,
+        "shared",
+        grouping = grouping,
+        groups = shared.len()
+    );
+
+    shared
+}
+
+fn total(input: &[String], grouping: Grouping) -> u32 {
+    shared(input, grouping)
+        .iter()
+        .map(|group| group.items.priority())
+        .sum()
+}
+
+pub fn part1(input: &[String]) -> u32 {
+    total(input, Grouping::HALVES)
+}
+
+pub fn part2(input: &[String]) -> u32 {
+    total(input, Grouping::TRIOS)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::{part1, part2, shared, Grouping};
+
+    const EXAMPLE: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw";
+
+    fn lines() -> Vec<String> {
+        EXAMPLE.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(&lines()), 157);
+        assert_eq!(part2(&lines()), 70);
+    }
+
+    #[test]
+    fn groups_of_one_share_everything() {
+        let groups = shared(&lines(), Grouping::Lines(NonZeroUsize::MIN));
+
+        assert_eq!(groups.len(), 6);
+        assert_eq!(groups[4].line, 5);
+        assert_eq!(groups[4].items.to_string().len(), 9);
+    }
 }
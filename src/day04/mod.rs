@@ -1,12 +1,57 @@
+use std::{cmp::Ordering, collections::HashMap};
+
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::{event, trace::Level};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
-struct Range(u32, u32);
+/** Print the most crowded section, the runs of covered sections, and overlaps between lines */
+pub fn sweep_report(input: Vec<String>, limit: usize) {
+    let assignments = assignments(&input);
+
+    match busiest_section(&assignments) {
+        Some((section, count)) => println!("Busiest section: {} ({} elves)", section, count),
+        None => println!("No assignments"),
+    }
+
+    let runs = covered_runs(&assignments);
+    println!("Covered runs: {}", runs.len());
+    for run in &runs {
+        println!("  {}-{}", run.0, run.1);
+    }
+
+    let pairs = overlapping_pairs(&assignments);
+    println!("Overlapping pairs across lines: {}", pairs.len());
+    for (a, b) in pairs.iter().take(limit) {
+        let (a, b) = (&assignments[*a], &assignments[*b]);
+        println!(
+            "  line {} elf {} ({}-{}) and line {} elf {} ({}-{})",
+            a.line, a.elf, a.range.0, a.range.1, b.line, b.elf, b.range.0, b.range.1
+        );
+    }
+    if pairs.len() > limit {
+        println!("  ...");
+    }
+}
+
+/** Inclusive range of section IDs */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Range(pub u32, pub u32);
+
+/** One elf's range, and where it came from */
+#[derive(Debug)]
+pub struct Assignment {
+    /** From 1 */
+    pub line: usize,
+    /** 1 or 2, its position in the pair */
+    pub elf: usize,
+    pub range: Range,
+}
 
 fn parse_input(line: &str) -> [Range; 2] {
     lazy_static! {
@@ -22,62 +67,293 @@ fn parse_input(line: &str) -> [Range; 2] {
     [Range(r1_start, r1_end), Range(r2_start, r2_end)]
 }
 
-fn is_subset(range1: Range, range2: Range) -> bool {
-    let r1_length = range1.1 - range1.0;
-    let r2_length = range2.1 - range2.0;
-    let bigger_range: Range;
-    let smaller_range: Range;
+/** Every elf's range in the file */
+pub fn assignments(input: &[String]) -> Vec<Assignment> {
+    input
+        .iter()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            parse_input(line)
+                .into_iter()
+                .enumerate()
+                .map(move |(elf, range)| Assignment {
+                    line: i + 1,
+                    elf: elf + 1,
+                    range,
+                })
+        })
+        .collect()
+}
+
+/** The first section covered by the most ranges, and how many that is */
+pub fn busiest_section(assignments: &[Assignment]) -> Option<(u32, usize)> {
+    // A range adds one from its start, then takes it away again just after its end
+    let mut events: Vec<(u64, i64)> = assignments
+        .iter()
+        .flat_map(|it| [(it.range.0 as u64, 1), (it.range.1 as u64 + 1, -1)])
+        .collect();
+    events.sort_unstable();
 
-    if r1_length > r2_length {
-        bigger_range = range1;
-        smaller_range = range2;
-    } else {
-        bigger_range = range2;
-        smaller_range = range1;
+    let mut busiest: Option<(u32, usize)> = None;
+    let mut count: i64 = 0;
+
+    for (section, change) in events {
+        count += change;
+
+        if busiest.is_none_or(|(_, most)| count as usize > most) {
+            busiest = Some((section as u32, count as usize));
+        }
     }
 
-    smaller_range.0 >= bigger_range.0 && smaller_range.1 <= bigger_range.1
+    busiest
 }
 
-fn has_overlap(range1: Range, range2: Range) -> bool {
-    let left_range: Range;
-    let right_range: Range;
+/** Maximal runs of sections that at least one elf is assigned to, in order */
+pub fn covered_runs(assignments: &[Assignment]) -> Vec<Range> {
+    let mut ranges: Vec<Range> = assignments.iter().map(|it| it.range).collect();
+    ranges.sort_unstable_by_key(|range| range.0);
+
+    let mut runs: Vec<Range> = vec![];
 
-    if range1.0 < range2.0 {
-        left_range = range1;
-        right_range = range2;
-    } else {
-        left_range = range2;
-        right_range = range1;
+    for range in ranges {
+        match runs.last_mut() {
+            // Sections are whole numbers, so touching ranges leave no gap
+            Some(run) if range.0 as u64 <= run.1 as u64 + 1 => run.1 = run.1.max(range.1),
+            _ => runs.push(range),
+        }
     }
 
-    right_range.0 <= left_range.1
+    runs
 }
 
-fn part1(input: &Vec<String>) -> u32 {
-    let mut result = 0;
+/**
+ * Indices of every pair of assignments on different lines that share a section. Sweeps the
+ * ranges by start, so it costs `O(n log n)` plus the number of pairs found
+ */
+pub fn overlapping_pairs(assignments: &[Assignment]) -> Vec<(usize, usize)> {
+    let mut order: Vec<usize> = (0..assignments.len()).collect();
+    order.sort_unstable_by_key(|&i| assignments[i].range.0);
+
+    let mut active: Vec<usize> = vec![];
+    let mut pairs = vec![];
 
-    for line in input {
-        let [range1, range2] = parse_input(line);
+    for i in order {
+        let current = &assignments[i];
+        // Whatever ended before this starts can't overlap anything from here on
+        active.retain(|&j| assignments[j].range.1 >= current.range.0);
 
-        if is_subset(range1, range2) {
-            result += 1;
+        for &j in &active {
+            if assignments[j].line != current.line {
+                pairs.push((j.min(i), j.max(i)));
+            }
         }
+
+        active.push(i);
     }
 
-    result
+    pairs.sort_unstable();
+    event!(
+        Level::Debug,
+        "swept",
+        assignments = assignments.len(),
+        pairs = pairs.len()
+    );
+
+    pairs
 }
 
-fn part2(input: &Vec<String>) -> u32 {
-    let mut result = 0;
+/**
+ * How two ranges sit relative to each other, from Allen's interval algebra. Reads as
+ * "the first range is `Before` the second", and so on
+ */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+pub const RELATIONS: [Relation; 13] = [
+    Relation::Before,
+    Relation::Meets,
+    Relation::Overlaps,
+    Relation::Starts,
+    Relation::During,
+    Relation::Finishes,
+    Relation::Equals,
+    Relation::FinishedBy,
+    Relation::Contains,
+    Relation::StartedBy,
+    Relation::OverlappedBy,
+    Relation::MetBy,
+    Relation::After,
+];
 
-    for line in input {
-        let [range1, range2] = parse_input(line);
+impl Relation {
+    /**
+     * Sections are whole numbers, so ranges are treated as covering `start..end + 1`. That makes
+     * ranges that touch without sharing a section, like `2-4` and `5-6`, meet
+     */
+    pub fn between(first: Range, second: Range) -> Relation {
+        let (a_start, a_end) = (first.0 as u64, first.1 as u64 + 1);
+        let (b_start, b_end) = (second.0 as u64, second.1 as u64 + 1);
 
-        if has_overlap(range1, range2) {
-            result += 1;
+        match (a_start.cmp(&b_start), a_end.cmp(&b_end)) {
+            (Ordering::Equal, Ordering::Equal) => Relation::Equals,
+            (Ordering::Equal, Ordering::Less) => Relation::Starts,
+            (Ordering::Equal, Ordering::Greater) => Relation::StartedBy,
+            (Ordering::Greater, Ordering::Equal) => Relation::Finishes,
+            (Ordering::Less, Ordering::Equal) => Relation::FinishedBy,
+            (Ordering::Greater, Ordering::Less) => Relation::During,
+            (Ordering::Less, Ordering::Greater) => Relation::Contains,
+            (Ordering::Less, Ordering::Less) => match a_end.cmp(&b_start) {
+                Ordering::Less => Relation::Before,
+                Ordering::Equal => Relation::Meets,
+                Ordering::Greater => Relation::Overlaps,
+            },
+            (Ordering::Greater, Ordering::Greater) => match a_start.cmp(&b_end) {
+                Ordering::Greater => Relation::After,
+                Ordering::Equal => Relation::MetBy,
+                Ordering::Less => Relation::OverlappedBy,
+            },
         }
     }
 
-    result
+    /** The relation with the ranges swapped */
+    pub fn inverse(self) -> Relation {
+        RELATIONS[RELATIONS.len() - 1 - RELATIONS.iter().position(|it| *it == self).unwrap()]
+    }
+
+    /** One range holds every section of the other */
+    pub fn is_containment(self) -> bool {
+        matches!(
+            self,
+            Relation::Starts
+                | Relation::During
+                | Relation::Finishes
+                | Relation::Equals
+                | Relation::FinishedBy
+                | Relation::Contains
+                | Relation::StartedBy
+        )
+    }
+
+    /** The ranges have at least one section in common */
+    pub fn shares_section(self) -> bool {
+        !matches!(
+            self,
+            Relation::Before | Relation::Meets | Relation::MetBy | Relation::After
+        )
+    }
+}
+
+/** How each line's pair relate */
+pub fn relations(input: &[String]) -> Vec<Relation> {
+    input
+        .iter()
+        .map(|line| {
+            let [first, second] = parse_input(line);
+            Relation::between(first, second)
+        })
+        .collect()
+}
+
+/** Count how many pairs fall under each relation */
+pub fn relation_report(input: Vec<String>) {
+    let relations = relations(&input);
+    let mut counts: HashMap<Relation, usize> = HashMap::new();
+
+    for relation in &relations {
+        *counts.entry(*relation).or_insert(0) += 1;
+    }
+
+    let widest = counts.values().max().copied().unwrap_or(1);
+    for relation in RELATIONS {
+        let count = counts.get(&relation).copied().unwrap_or(0);
+        println!(
+            "{:<13} {:>5} {}",
+            format!("{:?}", relation),
+            count,
+            "#".repeat(count * 40 / widest)
+        );
+    }
+}
+
+fn count(input: &[String], query: fn(Relation) -> bool) -> u32 {
+    relations(input)
+        .into_iter()
+        .filter(|relation| query(*relation))
+        .count() as u32
+}
+
+pub fn part1(input: &[String]) -> u32 {
+    count(input, Relation::is_containment)
+}
+
+pub fn part2(input: &[String]) -> u32 {
+    count(input, Relation::shares_section)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assignments, busiest_section, covered_runs, overlapping_pairs, part1, part2, Range,
+    };
+
+    const EXAMPLE: [&str; 6] = [
+        "2-4,6-8", "2-3,4-5", "5-7,7-9", "2-8,3-7", "6-6,4-6", "2-6,4-8",
+    ];
+
+    fn lines() -> Vec<String> {
+        EXAMPLE.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(&lines()), 2);
+        assert_eq!(part2(&lines()), 4);
+    }
+
+    #[test]
+    fn busiest_section_is_the_first_of_the_most() {
+        // Eight ranges have 6, next best are 4 and 5 with seven each
+        assert_eq!(busiest_section(&assignments(&lines())), Some((6, 8)));
+        assert_eq!(busiest_section(&[]), None);
+    }
+
+    #[test]
+    fn touching_ranges_make_one_run() {
+        let input = vec![String::from("1-2,3-4"), String::from("7-9,8-8")];
+
+        assert_eq!(
+            covered_runs(&assignments(&input)),
+            [Range(1, 4), Range(7, 9)]
+        );
+    }
+
+    #[test]
+    fn sweep_finds_the_same_pairs_as_checking_all_of_them() {
+        let assignments = assignments(&lines());
+        let mut expected = vec![];
+        for i in 0..assignments.len() {
+            for j in i + 1..assignments.len() {
+                let (a, b) = (assignments[i].range, assignments[j].range);
+                if assignments[i].line != assignments[j].line && a.0 <= b.1 && b.0 <= a.1 {
+                    expected.push((i, j));
+                }
+            }
+        }
+
+        assert_eq!(overlapping_pairs(&assignments), expected);
+    }
 }
@@ -1,149 +1,766 @@
-use std::collections::LinkedList;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    io::{self, BufRead, Write},
+};
 
 use regex::Regex;
 
+use crate::{event, trace::Level};
+
+use self::replay::Replay;
+
+pub mod replay;
+
 pub fn run(input: Vec<String>) {
-    println!("Part 1: {}", part1(&input));
-    println!("Part 2: {}", part2(&input));
+    match part1(&input) {
+        Ok(answer) => println!("Part 1: {}", answer),
+        Err(err) => eprintln!("Part 1: {}", err),
+    }
+
+    match part2(&input) {
+        Ok(answer) => println!("Part 2: {}", answer),
+        Err(err) => eprintln!("Part 2: {}", err),
+    }
 }
 
-#[derive(Debug)]
-struct Instruction {
-    count: u32,
-    from: u32,
-    to: u32,
+/**
+ * Rearrange the stacks with any crane: `9000`, `9001`, or a number of crates lifted at a time.
+ * Bad moves are handled by `policy`: `error`, `clamp` or `skip`
+ */
+pub fn run_with_crane(input: Vec<String>, crane: &str, policy: &str) -> Result<(), String> {
+    let crane = parse_crane(crane)?;
+    let policy = Policy::parse(policy)?;
+    let (Drawing { mut stacks, labels }, instructions) =
+        parse_input(&input).map_err(|err| err.to_string())?;
+
+    let issues = execute_checked(crane.as_ref(), &mut stacks, &instructions, policy)
+        .map_err(|issue| issue.report(&labels))?;
+
+    for issue in issues {
+        eprintln!("{}", issue.report(&labels));
+    }
+
+    println!("{}: {}", crane.name(), tops(&stacks, &labels));
+
+    Ok(())
 }
 
-fn parse_stack_line(line: &str) -> Vec<Option<char>> {
-    let mut result = Vec::new();
+/**
+ * Draw the stacks after the first `at` moves, or all of them. With `every`, draw them after
+ * each move along the way too
+ */
+pub fn diagram(
+    input: Vec<String>,
+    crane: &str,
+    at: Option<usize>,
+    every: bool,
+) -> Result<(), String> {
+    let crane = parse_crane(crane)?;
+    let (Drawing { mut stacks, labels }, instructions) =
+        parse_input(&input).map_err(|err| err.to_string())?;
+    let at = at.unwrap_or(instructions.len()).min(instructions.len());
+
+    for (i, instruction) in instructions[..at].iter().enumerate() {
+        if every {
+            print_drawing(&stacks, &labels);
+            println!(
+                "move {} from {} to {}",
+                instruction.count,
+                instruction.from + 1,
+                instruction.to + 1
+            );
+            println!();
+        }
 
-    let mut i = 0;
-    while i < line.chars().count() {
-        let slice = &line[i + 1..i + 2].chars().next();
+        execute(crane.as_ref(), &mut stacks, &instructions[i..=i]);
+    }
 
-        if let Some(val) = slice {
-            result.push(if val.is_alphabetic() {
-                Some(*val)
-            } else {
-                None
-            });
-        } else {
-            result.push(None);
+    print_drawing(&stacks, &labels);
+
+    Ok(())
+}
+
+/**
+ * Walk through the moves interactively: enter or `n` steps forward, `b` back, `j N` jumps to
+ * after move N, `t S` finds the last move to touch stack S, `f X [N]` finds crate X, now or
+ * after move N, and `q` quits
+ */
+pub fn step_mode(input: Vec<String>, crane: &str, commands: impl BufRead) -> Result<(), String> {
+    let crane = parse_crane(crane)?;
+    let (Drawing { stacks, labels }, instructions) =
+        parse_input(&input).map_err(|err| err.to_string())?;
+    let mut replay = Replay::new(crane.as_ref(), stacks, instructions);
+    let mut lines = commands.lines();
+
+    loop {
+        print_drawing(replay.stacks(), &labels);
+        match replay.next_instruction() {
+            Some(next) => println!(
+                "After {}/{}, next: move {} from {} to {}",
+                replay.position(),
+                replay.len(),
+                next.count,
+                next.from + 1,
+                next.to + 1
+            ),
+            None => println!("After {}/{}, done", replay.position(), replay.len()),
         }
 
-        i += 4;
+        loop {
+            print!("> ");
+            io::stdout().flush().map_err(|err| err.to_string())?;
+
+            let Some(Ok(line)) = lines.next() else {
+                return Ok(());
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = |i: usize| words.get(i).and_then(|it| it.parse::<usize>().ok());
+
+            match words.as_slice() {
+                [] | ["n"] => {
+                    replay.step();
+                }
+                ["b"] => {
+                    replay.undo();
+                }
+                ["j", _] if number(1).is_some() => replay.jump(number(1).unwrap()),
+                ["t", _] if number(1).is_some_and(|stack| stack > 0) => {
+                    match replay.last_touched(number(1).unwrap() - 1) {
+                        Some(instruction) => println!("Last touched by move {}", instruction),
+                        None => println!("Not touched yet"),
+                    }
+                    continue;
+                }
+                ["f", label] | ["f", label, _] => {
+                    let found = match (labels.find(label), number(2)) {
+                        (None, _) => vec![],
+                        (Some(item), Some(position)) => replay.find_at(item, position),
+                        (Some(item), None) => replay.find(item),
+                    };
+
+                    if found.is_empty() {
+                        println!("No crate {}", label);
+                    }
+                    for (stack, height) in found {
+                        println!("Stack {}, {} from the bottom", stack + 1, height + 1);
+                    }
+                    continue;
+                }
+                ["q"] => return Ok(()),
+                _ => {
+                    println!("Commands: n, b, j N, t S, f X [N], q");
+                    continue;
+                }
+            }
+
+            break;
+        }
     }
+}
 
-    result
+fn print_drawing(stacks: &[Stack], labels: &Labels) {
+    for line in render(stacks, labels) {
+        println!("{}", line);
+    }
 }
 
-fn parse_input(input: &[String]) -> (Vec<LinkedList<char>>, Vec<Instruction>) {
-    let mut iterator = input.iter();
-    let mut stacks: Vec<LinkedList<char>> = vec![];
+/** A crate, as its label's place in the drawing's `Labels` */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Crate(u32);
+
+/**
+ * The distinct labels in a drawing, which can be more than one letter. Each is kept once,
+ * however many crates carry it, so moving crates around never touches the labels
+ */
+#[derive(Debug, Default)]
+pub struct Labels {
+    labels: Vec<Box<str>>,
+    crates: HashMap<Box<str>, Crate>,
+}
 
-    for line in iterator.by_ref() {
-        let trimmed_line = line.trim();
-        if trimmed_line.is_empty() {
-            break; // Input separator
+impl Labels {
+    /** The crate labelled `label`, adding the label if it's new */
+    pub fn intern(&mut self, label: &str) -> Crate {
+        if let Some(item) = self.crates.get(label) {
+            return *item;
         }
-        if !trimmed_line.starts_with('[') {
-            continue; // Stack numbers
+
+        let item = Crate(u32::try_from(self.labels.len()).expect("Too many distinct labels"));
+        self.labels.push(label.into());
+        self.crates.insert(label.into(), item);
+
+        item
+    }
+
+    /** The crate labelled `label`, if any crate is */
+    pub fn find(&self, label: &str) -> Option<Crate> {
+        self.crates.get(label).copied()
+    }
+
+    pub fn label(&self, item: Crate) -> &str {
+        &self.labels[item.0 as usize]
+    }
+}
+
+/** Stacks read from a drawing, along with what their crates are labelled */
+#[derive(Debug, Default)]
+pub struct Drawing {
+    pub stacks: Vec<Stack>,
+    pub labels: Labels,
+}
+
+/** Crates, bottom first */
+pub type Stack = Vec<Crate>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    pub count: usize,
+    /** Index of the stack, from 0 */
+    pub from: usize,
+    /** Index of the stack, from 0 */
+    pub to: usize,
+}
+
+/** How a model of crane moves crates between stacks */
+pub trait Crane {
+    fn name(&self) -> String;
+
+    /** Move `count` crates off the top of `from`. The stacks are distinct, and `from` has enough */
+    fn lift(&self, from: &mut Stack, to: &mut Stack, count: usize);
+}
+
+/** One crate at a time, so a move reverses their order */
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        String::from("CrateMover 9000")
+    }
+
+    fn lift(&self, from: &mut Stack, to: &mut Stack, count: usize) {
+        let start = from.len() - count;
+        to.extend(from.drain(start..).rev());
+    }
+}
+
+/** Every crate in a move at once, keeping their order */
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        String::from("CrateMover 9001")
+    }
+
+    fn lift(&self, from: &mut Stack, to: &mut Stack, count: usize) {
+        let start = from.len() - count;
+        to.extend(from.drain(start..));
+    }
+}
+
+/** Up to a fixed number of crates at a time, each batch keeping its order */
+pub struct Batched(pub usize);
+
+impl Crane for Batched {
+    fn name(&self) -> String {
+        format!("{} at a time", self.0)
+    }
+
+    fn lift(&self, from: &mut Stack, to: &mut Stack, count: usize) {
+        let mut remaining = count;
+
+        while remaining > 0 {
+            let batch = remaining.min(self.0);
+            let start = from.len() - batch;
+            to.extend(from.drain(start..));
+            remaining -= batch;
         }
+    }
+}
 
-        let number_of_stacks = (line.chars().count() + 1) / 4;
+pub fn parse_crane(name: &str) -> Result<Box<dyn Crane>, String> {
+    match name {
+        "9000" => Ok(Box::new(CrateMover9000)),
+        "9001" => Ok(Box::new(CrateMover9001)),
+        batch => match batch.parse() {
+            Ok(0) | Err(_) => Err(format!("Unknown crane: {}", name)),
+            Ok(batch) => Ok(Box::new(Batched(batch))),
+        },
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidMove { line: usize, text: String },
+    InvalidDrawing { line: usize, text: String },
+}
 
-        if stacks.is_empty() {
-            for _ in 0..number_of_stacks {
-                stacks.push(LinkedList::new());
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidMove { line, text } => {
+                write!(f, "Line {}: '{}' isn't a move", line, text)
+            }
+            ParseError::InvalidDrawing { line, text } => {
+                write!(f, "Line {}: '{}' isn't part of a stack drawing", line, text)
             }
         }
+    }
+}
 
-        let stack_line = parse_stack_line(line);
+/** What to do with a move that can't be carried out as written */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Policy {
+    /** Stop at the first bad move */
+    Error,
+    /** Move as many crates as there are. Moves involving a missing stack are skipped */
+    Clamp,
+    /** Leave the stacks as they are */
+    Skip,
+}
 
-        for (i, val) in stack_line.iter().enumerate() {
-            if let Some(val) = val {
-                stacks.get_mut(i).unwrap().push_back(*val);
-            }
+impl Policy {
+    pub fn parse(name: &str) -> Result<Policy, String> {
+        match name {
+            "error" => Ok(Policy::Error),
+            "clamp" => Ok(Policy::Clamp),
+            "skip" => Ok(Policy::Skip),
+            _ => Err(format!("Unknown policy: {}", name)),
         }
     }
+}
 
-    let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-    let mut instructions = vec![];
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    /** Asked for more crates than the stack has */
+    Underflow {
+        stack: usize,
+        count: usize,
+        available: usize,
+    },
+    MissingStack(usize),
+}
 
-    for line in iterator.by_ref() {
-        let caps = re.captures(line).unwrap();
-        let count = caps.get(1).unwrap().as_str().parse::<u32>().unwrap();
-        let from = caps.get(2).unwrap().as_str().parse::<u32>().unwrap() - 1;
-        let to = caps.get(3).unwrap().as_str().parse::<u32>().unwrap() - 1;
+/** A bad move, along with the stacks it involves as they were just before it */
+#[derive(Debug)]
+pub struct Issue {
+    /** From 1 */
+    pub instruction: usize,
+    pub problem: Problem,
+    /** `(index, crates)` for each stack the move names that exists */
+    pub stacks: Vec<(usize, Stack)>,
+}
 
-        instructions.push(Instruction { count, to, from })
-    }
+impl Issue {
+    /** What went wrong, followed by a drawing of the stacks involved */
+    pub fn report(&self, labels: &Labels) -> String {
+        let columns: Vec<(usize, &Stack)> = self
+            .stacks
+            .iter()
+            .map(|(index, stack)| (*index, stack))
+            .collect();
+        let mut report = self.to_string();
+
+        for line in draw(&columns, labels) {
+            report.push('\n');
+            report.push_str(&line);
+        }
 
-    (stacks, instructions)
+        report
+    }
 }
 
-fn part1(input: &[String]) -> String {
-    let mut result = String::new();
-    let (mut stacks, instructions) = parse_input(input);
+impl Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instruction {}: ", self.instruction)?;
+
+        match self.problem {
+            Problem::Underflow {
+                stack,
+                count,
+                available,
+            } => write!(
+                f,
+                "can't move {} crates from stack {}, it only has {}",
+                count,
+                stack + 1,
+                available
+            ),
+            Problem::MissingStack(stack) => write!(f, "there's no stack {}", stack + 1),
+        }
+    }
+}
 
-    for instruction in instructions {
-        for _ in 0..instruction.count {
-            let mut temp: Option<char> = None;
+/** Carry out every instruction, cutting moves short when a stack runs out */
+pub fn execute(crane: &dyn Crane, stacks: &mut [Stack], instructions: &[Instruction]) {
+    // Clamping never fails
+    let _ = execute_checked(crane, stacks, instructions, Policy::Clamp);
+}
 
-            if let Some(from_stack) = stacks.get_mut(usize::try_from(instruction.from).unwrap()) {
-                if !from_stack.is_empty() {
-                    temp = Some(from_stack.pop_front().unwrap());
+/** Carry out every instruction, returning the bad moves that were clamped or skipped */
+pub fn execute_checked(
+    crane: &dyn Crane,
+    stacks: &mut [Stack],
+    instructions: &[Instruction],
+    policy: Policy,
+) -> Result<Vec<Issue>, Issue> {
+    let mut issues = vec![];
+
+    for (i, instruction) in instructions.iter().enumerate() {
+        let problem = check(stacks, instruction);
+
+        if let Some(problem) = problem {
+            let mut involved = vec![instruction.from, instruction.to];
+            involved.dedup();
+            let issue = Issue {
+                instruction: i + 1,
+                problem,
+                stacks: involved
+                    .into_iter()
+                    .filter_map(|index| Some((index, stacks.get(index)?.clone())))
+                    .collect(),
+            };
+            event!(
+                Level::Debug,
+                "bad move",
+                instruction = i + 1,
+                policy = policy
+            );
+
+            match (policy, &issue.problem) {
+                (Policy::Error, _) => return Err(issue),
+                (Policy::Clamp, Problem::Underflow { .. }) => issues.push(issue),
+                (Policy::Clamp, Problem::MissingStack(_)) | (Policy::Skip, _) => {
+                    issues.push(issue);
+                    continue;
                 }
             }
+        }
 
-            if let Some(to_stack) = stacks.get_mut(usize::try_from(instruction.to).unwrap()) {
-                if let Some(temp) = temp {
-                    to_stack.push_front(temp);
-                }
-            }
+        if let Some((from, to)) = pair_mut(stacks, instruction.from, instruction.to) {
+            let count = instruction.count.min(from.len());
+            crane.lift(from, to, count);
         }
     }
 
-    for stack in stacks {
-        let top = stack.front();
+    Ok(issues)
+}
 
-        if let Some(top) = top {
-            result.push(*top);
+fn check(stacks: &[Stack], instruction: &Instruction) -> Option<Problem> {
+    for stack in [instruction.from, instruction.to] {
+        if stack >= stacks.len() {
+            return Some(Problem::MissingStack(stack));
         }
     }
 
-    result
+    let available = stacks[instruction.from].len();
+    if instruction.count > available {
+        return Some(Problem::Underflow {
+            stack: instruction.from,
+            count: instruction.count,
+            available,
+        });
+    }
+
+    None
 }
 
-fn part2(input: &[String]) -> String {
-    let mut result = String::new();
-    let (mut stacks, instructions) = parse_input(input);
+/** Both stacks, if they exist and aren't the same one. Moving onto the same stack changes nothing */
+pub(crate) fn pair_mut(
+    stacks: &mut [Stack],
+    a: usize,
+    b: usize,
+) -> Option<(&mut Stack, &mut Stack)> {
+    if a == b || a >= stacks.len() || b >= stacks.len() {
+        return None;
+    }
 
-    for instruction in instructions {
-        let mut temp = LinkedList::new();
+    if a < b {
+        let (left, right) = stacks.split_at_mut(b);
+        Some((&mut left[a], &mut right[0]))
+    } else {
+        let (left, right) = stacks.split_at_mut(a);
+        Some((&mut right[0], &mut left[b]))
+    }
+}
 
-        if let Some(from_stack) = stacks.get_mut(usize::try_from(instruction.from).unwrap()) {
-            for _ in 0..instruction.count {
-                if !from_stack.is_empty() {
-                    temp.push_back(from_stack.pop_front().unwrap());
-                }
+/** The label of the crate on top of each stack. Empty stacks are left out */
+pub fn tops(stacks: &[Stack], labels: &Labels) -> String {
+    stacks
+        .iter()
+        .filter_map(|stack| stack.last())
+        .map(|item| labels.label(*item))
+        .collect()
+}
+
+/** Draw the stacks the way the puzzle input does, numbered footer included */
+pub fn render(stacks: &[Stack], labels: &Labels) -> Vec<String> {
+    let columns: Vec<(usize, &Stack)> = stacks.iter().enumerate().collect();
+
+    draw(&columns, labels)
+}
+
+/** Draw some of the stacks, each numbered by its index */
+fn draw(columns: &[(usize, &Stack)], labels: &Labels) -> Vec<String> {
+    let label_width = columns
+        .iter()
+        .flat_map(|(_, stack)| stack.iter())
+        .map(|item| labels.label(*item).chars().count())
+        .max()
+        .unwrap_or(1);
+    let number_width = columns
+        .iter()
+        .map(|(index, _)| (index + 1).to_string().len())
+        .max()
+        .unwrap_or(1);
+    let width = (label_width + 2).max(number_width);
+    let height = columns
+        .iter()
+        .map(|(_, stack)| stack.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut lines: Vec<String> = (0..height)
+        .rev()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|(_, stack)| match stack.get(row) {
+                    Some(item) => format!("{:^width$}", format!("[{}]", labels.label(*item))),
+                    None => " ".repeat(width),
+                })
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect();
+
+    lines.push(
+        columns
+            .iter()
+            .map(|(index, _)| format!("{:^width$}", index + 1))
+            .collect::<Vec<String>>()
+            .join(" "),
+    );
+
+    lines
+}
+
+/** Each whitespace separated token in `line`, with the columns of its first and last character */
+fn tokens(line: &str) -> Vec<(usize, usize, String)> {
+    let mut tokens: Vec<(usize, usize, String)> = vec![];
+
+    for (column, c) in line.chars().enumerate() {
+        match tokens.last_mut() {
+            _ if c.is_whitespace() => {}
+            Some((_, end, text)) if *end + 1 == column => {
+                *end = column;
+                text.push(c);
             }
+            _ => tokens.push((column, column, c.to_string())),
         }
+    }
 
-        if let Some(to_stack) = stacks.get_mut(usize::try_from(instruction.to).unwrap()) {
-            while !temp.is_empty() {
-                to_stack.push_front(temp.pop_back().unwrap());
-            }
+    tokens
+}
+
+/**
+ * Read a stack drawing, bottom line the stack numbers. Columns come from where the numbers are,
+ * so there can be any number of stacks, and labels of any length
+ */
+pub fn parse_drawing(lines: &[String]) -> Result<Drawing, ParseError> {
+    let error = |i: usize| ParseError::InvalidDrawing {
+        line: i + 1,
+        text: lines[i].to_string(),
+    };
+
+    let Some((footer, rows)) = lines.split_last() else {
+        return Ok(Drawing::default());
+    };
+    let numbers = tokens(footer);
+    let centres: Vec<usize> = numbers.iter().map(|(start, end, _)| start + end).collect();
+
+    for (i, (_, _, number)) in numbers.iter().enumerate() {
+        if number.parse() != Ok(i + 1) {
+            return Err(error(rows.len()));
         }
     }
 
-    for stack in stacks {
-        let top = stack.front();
+    let mut stacks: Vec<Stack> = vec![Stack::new(); numbers.len()];
+    let mut labels = Labels::default();
+
+    for (i, row) in rows.iter().enumerate() {
+        for (start, end, text) in tokens(row) {
+            let label = text
+                .strip_prefix('[')
+                .and_then(|it| it.strip_suffix(']'))
+                .filter(|it| !it.is_empty())
+                .ok_or_else(|| error(i))?;
+            // Doubled, like `centres`, to stay in whole numbers
+            let centre = start + end;
+            let stack = (0..centres.len())
+                .min_by_key(|&stack| centres[stack].abs_diff(centre))
+                .ok_or_else(|| error(i))?;
+
+            stacks[stack].push(labels.intern(label));
+        }
+    }
+
+    // Drawn top down, but stored bottom up
+    for stack in &mut stacks {
+        stack.reverse();
+    }
+
+    Ok(Drawing { stacks, labels })
+}
+
+pub fn parse_input(input: &[String]) -> Result<(Drawing, Vec<Instruction>), ParseError> {
+    let drawing_length = input
+        .iter()
+        .position(|line| line.trim().is_empty())
+        .unwrap_or(input.len());
+    let drawing = parse_drawing(&input[..drawing_length])?;
+    let iterator = input.iter().enumerate().skip(drawing_length + 1);
+
+    let re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
+    let mut instructions = vec![];
 
-        if let Some(top) = top {
-            result.push(*top);
+    for (i, line) in iterator {
+        if line.trim().is_empty() {
+            continue;
         }
+
+        let error = || ParseError::InvalidMove {
+            line: i + 1,
+            text: line.to_string(),
+        };
+        let caps = re.captures(line.trim()).ok_or_else(error)?;
+        let number = |group: usize| caps[group].parse::<usize>().map_err(|_| error());
+        // Stacks are numbered from 1
+        let stack = |group: usize| number(group)?.checked_sub(1).ok_or_else(error);
+
+        instructions.push(Instruction {
+            count: number(1)?,
+            from: stack(2)?,
+            to: stack(3)?,
+        })
     }
 
-    result
+    event!(
+        Level::Debug,
+        "parsed",
+        stacks = drawing.stacks.len(),
+        labels = drawing.labels.labels.len(),
+        instructions = instructions.len()
+    );
+
+    Ok((drawing, instructions))
+}
+
+fn solve(input: &[String], crane: &dyn Crane) -> Result<String, ParseError> {
+    let (Drawing { mut stacks, labels }, instructions) = parse_input(input)?;
+    execute(crane, &mut stacks, &instructions);
+
+    Ok(tops(&stacks, &labels))
+}
+
+pub fn part1(input: &[String]) -> Result<String, ParseError> {
+    solve(input, &CrateMover9000)
+}
+
+pub fn part2(input: &[String]) -> Result<String, ParseError> {
+    solve(input, &CrateMover9001)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        execute_checked, parse_drawing, parse_input, part1, part2, render, CrateMover9001, Policy,
+        Problem,
+    };
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(&lines(EXAMPLE)), Ok(String::from("CMZ")));
+        assert_eq!(part2(&lines(EXAMPLE)), Ok(String::from("MCD")));
+    }
+
+    #[test]
+    fn drawings_round_trip() {
+        for drawing in [
+            &lines(EXAMPLE)[..4],
+            &lines(
+                "     [Fe]                    
+[Cu] [Zn]           [Au]     
+[Ag] [Cu] [Fe] [Sn] [Pb] [Cu]
+ 1    2    3    4    5    6  ",
+            )[..],
+        ] {
+            let parsed = parse_drawing(drawing).unwrap();
+
+            assert_eq!(render(&parsed.stacks, &parsed.labels), drawing);
+        }
+    }
+
+    #[test]
+    fn labels_are_stored_once() {
+        let drawing = parse_drawing(&lines("[A] [B]\n[B] [A]\n 1   2 ")).unwrap();
+
+        assert_eq!(drawing.labels.labels.len(), 2);
+        assert_eq!(drawing.stacks[0][1], drawing.stacks[1][0]);
+        assert_eq!(drawing.labels.find("A"), Some(drawing.stacks[0][1]));
+        assert_eq!(drawing.labels.find("C"), None);
+    }
+
+    #[test]
+    fn issues_keep_only_the_stacks_involved() {
+        let (mut drawing, _) = parse_input(&lines(EXAMPLE)).unwrap();
+        let instructions = parse_input(&lines("\nmove 3 from 3 to 1\nmove 1 from 4 to 2"))
+            .unwrap()
+            .1;
+
+        let issues = execute_checked(
+            &CrateMover9001,
+            &mut drawing.stacks,
+            &instructions,
+            Policy::Skip,
+        )
+        .unwrap();
+
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[0].problem,
+            Problem::Underflow {
+                stack: 2,
+                count: 3,
+                available: 1
+            }
+        );
+        assert_eq!(
+            issues[0].stacks.iter().map(|(i, _)| *i).collect::<Vec<_>>(),
+            [2, 0]
+        );
+        assert_eq!(
+            issues[0].report(&drawing.labels),
+            "Instruction 1: can't move 3 crates from stack 3, it only has 1
+    [N]
+[P] [Z]
+ 3   1 "
+        );
+        assert_eq!(issues[1].problem, Problem::MissingStack(3));
+        assert_eq!(issues[1].stacks.len(), 1);
+    }
 }
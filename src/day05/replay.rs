@@ -0,0 +1,145 @@
+use super::{pair_mut, Crane, Crate, Instruction, Stack};
+
+/** What a move did, so it can be taken back */
+#[derive(Debug)]
+struct Effect {
+    from: usize,
+    to: usize,
+    /** The crates lifted, as they were stacked on `from`. Empty if the move was skipped */
+    moved: Vec<Crate>,
+}
+
+/**
+ * Steps through the instructions in either direction. Moves are cut short when a stack runs out,
+ * and skipped when a stack doesn't exist, as with `execute`
+ */
+pub struct Replay<'a> {
+    crane: &'a dyn Crane,
+    instructions: Vec<Instruction>,
+    stacks: Vec<Stack>,
+    /** One per instruction carried out so far */
+    log: Vec<Effect>,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(crane: &'a dyn Crane, stacks: Vec<Stack>, instructions: Vec<Instruction>) -> Self {
+        Replay {
+            crane,
+            instructions,
+            stacks,
+            log: vec![],
+        }
+    }
+
+    pub fn stacks(&self) -> &[Stack] {
+        &self.stacks
+    }
+
+    /** How many instructions have been carried out */
+    pub fn position(&self) -> usize {
+        self.log.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    /** The instruction that will be carried out next */
+    pub fn next_instruction(&self) -> Option<&Instruction> {
+        self.instructions.get(self.position())
+    }
+
+    /** Carry out the next instruction. False if there aren't any left */
+    pub fn step(&mut self) -> bool {
+        let Some(instruction) = self.instructions.get(self.log.len()).copied() else {
+            return false;
+        };
+
+        let moved = match pair_mut(&mut self.stacks, instruction.from, instruction.to) {
+            Some((from, to)) => {
+                let count = instruction.count.min(from.len());
+                let moved = from[from.len() - count..].to_vec();
+                self.crane.lift(from, to, count);
+
+                moved
+            }
+            None => vec![],
+        };
+
+        self.log.push(Effect {
+            from: instruction.from,
+            to: instruction.to,
+            moved,
+        });
+
+        true
+    }
+
+    /** Take back the last instruction carried out. False if there's nothing to take back */
+    pub fn undo(&mut self) -> bool {
+        let Some(effect) = self.log.pop() else {
+            return false;
+        };
+
+        if let Some((from, to)) = pair_mut(&mut self.stacks, effect.from, effect.to) {
+            to.truncate(to.len() - effect.moved.len());
+            from.extend(effect.moved);
+        }
+
+        true
+    }
+
+    /** Step or undo until `position` instructions have been carried out */
+    pub fn jump(&mut self, position: usize) {
+        let position = position.min(self.len());
+
+        while self.position() < position {
+            self.step();
+        }
+        while self.position() > position {
+            self.undo();
+        }
+    }
+
+    /**
+     * The last instruction carried out, numbered from 1, that moved crates off or onto `stack`,
+     * an index from 0
+     */
+    pub fn last_touched(&self, stack: usize) -> Option<usize> {
+        self.log
+            .iter()
+            .rposition(|effect| {
+                !effect.moved.is_empty() && (effect.from == stack || effect.to == stack)
+            })
+            .map(|i| i + 1)
+    }
+
+    /** `(stack, height)` of every crate like `item`, heights from 0 at the bottom */
+    pub fn find(&self, item: Crate) -> Vec<(usize, usize)> {
+        self.stacks
+            .iter()
+            .enumerate()
+            .flat_map(|(stack, crates)| {
+                crates
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, it)| **it == item)
+                    .map(move |(height, _)| (stack, height))
+            })
+            .collect()
+    }
+
+    /** Like `find`, but after the first `position` instructions. Comes back to where it was */
+    pub fn find_at(&mut self, item: Crate, position: usize) -> Vec<(usize, usize)> {
+        let current = self.position();
+        self.jump(position);
+        let found = self.find(item);
+        self.jump(current);
+
+        found
+    }
+}
@@ -1,42 +1,329 @@
-use std::collections::{HashSet, VecDeque};
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, ErrorKind, Read},
+};
+
+use crate::{event, trace::Level};
 
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input).unwrap());
     println!("Part 2: {}", part2(&input).unwrap());
 }
 
-fn is_marker(marker_size: usize, window: &VecDeque<char>) -> bool {
-    let set = HashSet::<char>::from_iter(window.iter().copied());
+/** Print every marker and message in `reader` as they're found */
+pub fn decode(reader: impl Read, packet_size: usize, message_size: usize) -> io::Result<()> {
+    for event in Decoder::new(reader, packet_size, message_size)? {
+        match event? {
+            Event::Packet(offset) => println!("Packet marker at {}", offset),
+            Event::Message(offset) => println!("Message marker at {}", offset),
+            Event::Body(body) => println!(
+                "Message of {} bytes: {}",
+                body.len(),
+                String::from_utf8_lossy(&body)
+            ),
+        }
+    }
 
-    set.len() == marker_size
+    Ok(())
 }
 
-fn solve(marker_size: usize, input: &[String]) -> Option<u32> {
-    let mut window = VecDeque::new();
-    let signal = input.iter().next().unwrap();
+/**
+ * The last `size` bytes, along with a count of each byte among them and of how many of those
+ * are repeats, so checking for a marker doesn't mean looking through the window
+ */
+pub struct Window {
+    size: usize,
+    bytes: VecDeque<u8>,
+    counts: [usize; 256],
+    repeats: usize,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Window {
+        Window {
+            size,
+            bytes: VecDeque::with_capacity(size + 1),
+            counts: [0; 256],
+            repeats: 0,
+        }
+    }
+
+    /** Slide the window on by `byte`. True if it's now full of different bytes */
+    pub fn push(&mut self, byte: u8) -> bool {
+        self.bytes.push_back(byte);
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 2 {
+            self.repeats += 1;
+        }
+
+        if self.bytes.len() > self.size {
+            let leaving = self.bytes.pop_front().unwrap() as usize;
+            self.counts[leaving] -= 1;
+            if self.counts[leaving] == 1 {
+                self.repeats -= 1;
+            }
+        }
+
+        self.bytes.len() == self.size && self.repeats == 0
+    }
+
+    /** Empty the window, so the next marker can't share any bytes with the last */
+    pub fn clear(&mut self) {
+        for byte in self.bytes.drain(..) {
+            self.counts[byte as usize] = 0;
+        }
+        self.repeats = 0;
+    }
+}
+
+/** How many bytes have been read when the last `marker_size` of them are all different */
+pub fn find_marker(signal: &[u8], marker_size: usize) -> Option<usize> {
+    if marker_size == 0 {
+        return Some(0);
+    }
+
+    let mut window = Window::new(marker_size);
+
+    signal
+        .iter()
+        .position(|byte| window.push(*byte))
+        .map(|i| i + 1)
+}
+
+/** Something found in the stream. Offsets count the signal's bytes, line breaks left out */
+#[derive(Debug, PartialEq, Eq)]
+pub enum Event {
+    /** A start-of-packet marker ends here */
+    Packet(usize),
+    /** A start-of-message marker ends here */
+    Message(usize),
+    /** Everything between one start-of-message marker and the next, or the end of the stream */
+    Body(Vec<u8>),
+}
+
+/**
+ * Finds every marker in a stream of any length, a chunk at a time. Once a marker is found, the
+ * next one of its kind has to be made up of later bytes. Line breaks are skipped, so a capture
+ * can be split over many lines
+ */
+pub struct Decoder<R> {
+    reader: io::BufReader<R>,
+    packet: Window,
+    message: Window,
+    message_size: usize,
+    /** Signal bytes read so far */
+    offset: usize,
+    /** The current message, once its marker has been seen */
+    body: Option<Vec<u8>>,
+    pending: VecDeque<Event>,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /** Markers have to be at least a byte long, or there'd be one everywhere */
+    pub fn new(reader: R, packet_size: usize, message_size: usize) -> io::Result<Decoder<R>> {
+        if packet_size == 0 || message_size == 0 {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "Marker sizes must be at least 1",
+            ));
+        }
+
+        Ok(Decoder {
+            reader: io::BufReader::new(reader),
+            packet: Window::new(packet_size),
+            message: Window::new(message_size),
+            message_size,
+            offset: 0,
+            body: None,
+            pending: VecDeque::new(),
+            done: false,
+        })
+    }
+
+    fn consume(&mut self, byte: u8) {
+        if byte == b'\n' || byte == b'\r' {
+            return;
+        }
+
+        self.offset += 1;
+
+        if let Some(body) = &mut self.body {
+            body.push(byte);
+        }
 
-    // Pre-populate with first three chars
-    for c in signal.chars().take(marker_size - 1) {
-        window.push_back(c);
+        if self.packet.push(byte) {
+            self.packet.clear();
+            self.pending.push_back(Event::Packet(self.offset));
+        }
+
+        if self.message.push(byte) {
+            self.message.clear();
+
+            if let Some(mut body) = self.body.take() {
+                // The new marker isn't part of the last message
+                body.truncate(body.len().saturating_sub(self.message_size));
+                self.pending.push_back(Event::Body(body));
+            }
+
+            self.body = Some(vec![]);
+            self.pending.push_back(Event::Message(self.offset));
+        }
     }
+}
+
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = io::Result<Event>;
 
-    for (i, c) in signal.chars().enumerate().skip(3) {
-        window.push_back(c);
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && !self.done {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk.to_vec(),
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Some(Err(err)),
+            };
 
-        if is_marker(marker_size, &window) {
-            return Some((i + 1).try_into().unwrap());
+            if chunk.is_empty() {
+                self.done = true;
+                if let Some(body) = self.body.take() {
+                    self.pending.push_back(Event::Body(body));
+                }
+                event!(Level::Debug, "decoded", bytes = self.offset);
+                break;
+            }
+
+            self.reader.consume(chunk.len());
+            for byte in chunk {
+                self.consume(byte);
+            }
         }
 
-        window.pop_front();
+        self.pending.pop_front().map(Ok)
     }
+}
 
-    None
+fn solve(marker_size: usize, input: &[String]) -> Option<u32> {
+    let signal = input.first()?;
+
+    find_marker(signal.as_bytes(), marker_size).map(|position| position as u32)
 }
 
-fn part1(input: &[String]) -> Option<u32> {
+pub fn part1(input: &[String]) -> Option<u32> {
     solve(4, input)
 }
 
-fn part2(input: &[String]) -> Option<u32> {
+pub fn part2(input: &[String]) -> Option<u32> {
     solve(14, input)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{find_marker, Decoder, Event};
+
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    /** Checks every window from scratch */
+    fn naive(signal: &[u8], marker_size: usize) -> Option<usize> {
+        (marker_size..=signal.len()).find(|&end| {
+            let window = &signal[end - marker_size..end];
+            window
+                .iter()
+                .enumerate()
+                .all(|(i, byte)| !window[i + 1..].contains(byte))
+        })
+    }
+
+    #[test]
+    fn size_1_is_the_first_byte() {
+        assert_eq!(find_marker(b"aaab", 1), Some(1));
+        assert_eq!(find_marker(b"", 1), None);
+    }
+
+    #[test]
+    fn size_4() {
+        for (signal, expected, _) in EXAMPLES {
+            assert_eq!(
+                find_marker(signal.as_bytes(), 4),
+                Some(expected),
+                "{}",
+                signal
+            );
+        }
+    }
+
+    #[test]
+    fn size_14() {
+        for (signal, _, expected) in EXAMPLES {
+            assert_eq!(
+                find_marker(signal.as_bytes(), 14),
+                Some(expected),
+                "{}",
+                signal
+            );
+        }
+    }
+
+    #[test]
+    fn larger_sizes() {
+        let alphabet = b"abcdefghijklmnopqrstuvwxyz";
+        let mut signal = b"abcabcabc".to_vec();
+        signal.extend_from_slice(alphabet);
+
+        assert_eq!(find_marker(&signal, 26), Some(signal.len()));
+        assert_eq!(find_marker(&signal, 27), None);
+        assert_eq!(find_marker(alphabet, 100), None);
+    }
+
+    #[test]
+    fn matches_naive_search() {
+        // Small alphabets, so markers turn up late or not at all
+        let mut state: u32 = 1;
+        for _ in 0..200 {
+            let signal: Vec<u8> = (0..300)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    b'a' + (state >> 16) as u8 % 16
+                })
+                .collect();
+
+            for marker_size in [1, 2, 4, 8, 14, 16, 17] {
+                assert_eq!(
+                    find_marker(&signal, marker_size),
+                    naive(&signal, marker_size)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decoder_splits_messages_across_lines() {
+        let capture = "mjqjpqmgbljsphdztnvjfqwrcgsmlb\nhello there\nabcdefghijklmnopqrst\n";
+        let events: Vec<Event> = Decoder::new(capture.as_bytes(), 4, 14)
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|event| !matches!(event, Event::Packet(_)))
+            .collect();
+
+        assert_eq!(
+            events,
+            [
+                Event::Message(19),
+                Event::Body(b"jfqwrcgsmlbhello there".to_vec()),
+                Event::Message(55),
+                Event::Body(b"opqrst".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn decoder_rejects_empty_markers() {
+        assert!(Decoder::new(&b"abc"[..], 0, 14).is_err());
+        assert!(Decoder::new(&b"abc"[..], 4, 0).is_err());
+    }
+}
@@ -0,0 +1,412 @@
+use crate::json::{self, Value};
+
+/** Index of a node in its `FileSystem` */
+pub type NodeId = usize;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Directory,
+    File,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub name: String,
+    pub kind: Kind,
+    pub parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    /** A file's own size, or everything under a directory */
+    size: usize,
+}
+
+/** An entry that's already there, but isn't what was being added */
+#[derive(Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub existing: NodeId,
+}
+
+/**
+ * A directory tree, with every node kept in one `Vec` and linked by index. Directory sizes are
+ * kept up to date as files are added, so reading one is free
+ */
+#[derive(Debug)]
+pub struct FileSystem {
+    nodes: Vec<Node>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileSystem {
+    pub const ROOT: NodeId = 0;
+
+    /** Just an empty root directory */
+    pub fn new() -> FileSystem {
+        FileSystem {
+            nodes: vec![Node {
+                name: String::from("/"),
+                kind: Kind::Directory,
+                parent: None,
+                children: vec![],
+                size: 0,
+            }],
+        }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /** There's always a root, so never */
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn is_directory(&self, id: NodeId) -> bool {
+        self.nodes[id].kind == Kind::Directory
+    }
+
+    /** A file's size, or the total of everything under a directory */
+    pub fn size(&self, id: NodeId) -> usize {
+        self.nodes[id].size
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id].parent
+    }
+
+    pub fn children(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes[id].children.iter().copied()
+    }
+
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id)
+            .find(|child| self.nodes[*child].name == name)
+    }
+
+    /** Add a directory to `parent`, or find the one that's already there */
+    pub fn add_directory(&mut self, parent: NodeId, name: &str) -> Result<NodeId, Conflict> {
+        self.add(parent, name, Kind::Directory, 0)
+    }
+
+    /** Add a file to `parent`, or find the same one that's already there */
+    pub fn add_file(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        size: usize,
+    ) -> Result<NodeId, Conflict> {
+        self.add(parent, name, Kind::File, size)
+    }
+
+    fn add(
+        &mut self,
+        parent: NodeId,
+        name: &str,
+        kind: Kind,
+        size: usize,
+    ) -> Result<NodeId, Conflict> {
+        if let Some(existing) = self.child(parent, name) {
+            let node = &self.nodes[existing];
+
+            return match (node.kind, kind) {
+                (Kind::Directory, Kind::Directory) => Ok(existing),
+                (Kind::File, Kind::File) if node.size == size => Ok(existing),
+                _ => Err(Conflict { existing }),
+            };
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            kind,
+            parent: Some(parent),
+            children: vec![],
+            size,
+        });
+        self.nodes[parent].children.push(id);
+
+        let mut ancestor = Some(parent);
+        while let Some(directory) = ancestor {
+            self.nodes[directory].size += size;
+            ancestor = self.nodes[directory].parent;
+        }
+
+        Ok(id)
+    }
+
+    /**
+     * Follow `path` from `from`, or from the root if it starts with `/`. Understands `.` and `..`,
+     * where going above the root stays at the root
+     */
+    pub fn resolve(&self, from: NodeId, path: &str) -> Option<NodeId> {
+        let start = if path.starts_with('/') {
+            Self::ROOT
+        } else {
+            from
+        };
+
+        path.split('/')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(start, |current, segment| match segment {
+                "." => Some(current),
+                ".." => Some(self.parent(current).unwrap_or(Self::ROOT)),
+                name => self.child(current, name),
+            })
+    }
+
+    /** Find an absolute path, like `/a/b/c` */
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        self.resolve(Self::ROOT, path)
+    }
+
+    /** Absolute path of `id` */
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names: Vec<&str> = self
+            .ancestors(id)
+            .filter(|it| *it != Self::ROOT)
+            .map(|it| self.nodes[it].name.as_str())
+            .collect();
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    /** `id`, then its parent, and so on up to the root */
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |it| self.parent(*it))
+    }
+
+    /** How many directories up the root is */
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.ancestors(id).count() - 1
+    }
+
+    /** `id` and everything under it, depth first, each directory before its contents */
+    pub fn walk(&self, id: NodeId) -> Walk<'_> {
+        Walk {
+            file_system: self,
+            stack: vec![id],
+        }
+    }
+
+    /**
+     * `{"name", "size", "children"}` for directories, and the same without children for files.
+     * Each directory level is two levels of JSON, so a tree more than half of `json::MAX_DEPTH`
+     * deep is refused, as it couldn't be read back in
+     */
+    pub fn to_json(&self, id: NodeId) -> Result<Value, String> {
+        let entry = |node: NodeId, children: Option<Vec<Value>>| {
+            let node = &self.nodes[node];
+            let mut entries = vec![
+                (String::from("name"), Value::String(node.name.clone())),
+                (String::from("size"), Value::Number(node.size as f64)),
+            ];
+            if let Some(children) = children {
+                entries.push((String::from("children"), Value::Array(children)));
+            }
+
+            Value::Object(entries)
+        };
+
+        // Children are finished before their directory, which then takes them off the end
+        let mut finished: Vec<Value> = vec![];
+        let mut stack = vec![Visit::Enter { id, depth: 0 }];
+
+        while let Some(visit) = stack.pop() {
+            match visit {
+                Visit::Enter { id, depth } => {
+                    let levels = 2 * depth + if self.is_directory(id) { 2 } else { 1 };
+                    if levels > json::MAX_DEPTH {
+                        return Err(format!("'{}' is too deep to write as JSON", self.path(id)));
+                    }
+
+                    if !self.is_directory(id) {
+                        finished.push(entry(id, None));
+                        continue;
+                    }
+
+                    stack.push(Visit::Leave(id));
+                    // Reversed, so they're finished in order
+                    for child in self.nodes[id].children.iter().rev() {
+                        stack.push(Visit::Enter {
+                            id: *child,
+                            depth: depth + 1,
+                        });
+                    }
+                }
+                Visit::Leave(id) => {
+                    let start = finished.len() - self.nodes[id].children.len();
+                    let children = finished.split_off(start);
+                    finished.push(entry(id, Some(children)));
+                }
+            }
+        }
+
+        Ok(finished.pop().unwrap())
+    }
+
+    /**
+     * Inverse of `to_json`. Directory sizes are worked out again, rather than trusted, and names
+     * have to be ones a transcript could use
+     */
+    pub fn from_json(value: &Value) -> Result<FileSystem, String> {
+        let mut file_system = FileSystem::new();
+        let children = value
+            .get("children")
+            .and_then(Value::as_array)
+            .ok_or("The root needs to be a directory")?;
+        let mut stack: Vec<(NodeId, &Value)> = children
+            .iter()
+            .rev()
+            .map(|child| (Self::ROOT, child))
+            .collect();
+
+        while let Some((parent, value)) = stack.pop() {
+            let name = value
+                .get("name")
+                .and_then(Value::as_str)
+                .filter(|name| is_valid_name(name))
+                .ok_or_else(|| format!("Missing or invalid name: {}", value))?;
+            let conflict = format!("'{}' is in {} twice", name, file_system.path(parent));
+
+            match value.get("children") {
+                Some(children) => {
+                    let children = children
+                        .as_array()
+                        .ok_or_else(|| format!("Children of '{}' aren't a list", name))?;
+                    let id = file_system
+                        .add_directory(parent, name)
+                        .map_err(|_| conflict)?;
+
+                    // Reversed, so they're added in order
+                    stack.extend(children.iter().rev().map(|child| (id, child)));
+                }
+                None => {
+                    let size = value
+                        .get("size")
+                        .and_then(Value::as_usize)
+                        .ok_or_else(|| format!("Missing or invalid size of '{}'", name))?;
+                    file_system
+                        .add_file(parent, name, size)
+                        .map_err(|_| conflict)?;
+                }
+            }
+        }
+
+        Ok(file_system)
+    }
+
+    /** Every directory, starting with the root */
+    pub fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.walk(Self::ROOT).filter(|it| self.is_directory(*it))
+    }
+}
+
+/** Not empty, not `.` or `..`, and no `/` or whitespace, or `cd` and `ls` couldn't say it */
+pub(super) fn is_valid_name(name: &str) -> bool {
+    !matches!(name, "" | "." | "..") && !name.contains(|c: char| c == '/' || c.is_whitespace())
+}
+
+enum Visit {
+    /** Write out a node `depth` levels below where writing started */
+    Enter { id: NodeId, depth: usize },
+    /** Everything in the directory has been written */
+    Leave(NodeId),
+}
+
+pub struct Walk<'a> {
+    file_system: &'a FileSystem,
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+        // Reversed, so children come out in the order they were added
+        self.stack
+            .extend(self.file_system.nodes[id].children.iter().rev());
+
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FileSystem;
+    use crate::json::{self, Value};
+
+    fn example() -> FileSystem {
+        let mut file_system = FileSystem::new();
+        let a = file_system.add_directory(FileSystem::ROOT, "a").unwrap();
+        let e = file_system.add_directory(a, "e").unwrap();
+        file_system.add_file(e, "i", 584).unwrap();
+        file_system.add_file(a, "f", 29116).unwrap();
+        file_system
+            .add_file(FileSystem::ROOT, "b.txt", 14848514)
+            .unwrap();
+        file_system.add_directory(FileSystem::ROOT, "d").unwrap();
+
+        file_system
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let file_system = example();
+        let value = file_system.to_json(FileSystem::ROOT).unwrap();
+        let read = FileSystem::from_json(&json::parse(&value.pretty()).unwrap()).unwrap();
+
+        assert_eq!(read.to_json(FileSystem::ROOT), Ok(value));
+        assert_eq!(read.size(read.lookup("/a").unwrap()), 29700);
+        assert!(read.is_directory(read.lookup("/d").unwrap()));
+    }
+
+    #[test]
+    fn from_json_rejects_bad_names() {
+        for name in ["", ".", "..", "/", "a/b", "a b", "tab\t"] {
+            let value = Value::Object(vec![(
+                String::from("children"),
+                Value::Array(vec![Value::Object(vec![
+                    (String::from("name"), Value::String(name.to_string())),
+                    (String::from("size"), Value::Number(1.0)),
+                ])]),
+            )]);
+
+            assert!(FileSystem::from_json(&value).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn from_json_rejects_duplicates() {
+        let text = r#"{"children":[{"name":"a","size":1},{"name":"a","children":[]}]}"#;
+
+        assert_eq!(
+            FileSystem::from_json(&json::parse(text).unwrap()).err(),
+            Some(String::from("'a' is in / twice"))
+        );
+    }
+
+    #[test]
+    fn to_json_refuses_trees_too_deep_to_read_back() {
+        let mut file_system = FileSystem::new();
+        let mut directory = FileSystem::ROOT;
+        for _ in 0..json::MAX_DEPTH / 2 - 1 {
+            directory = file_system.add_directory(directory, "a").unwrap();
+        }
+
+        let value = file_system.to_json(FileSystem::ROOT).unwrap();
+        assert!(json::parse(&value.to_string()).is_ok());
+
+        file_system.add_directory(directory, "a").unwrap();
+        assert!(file_system.to_json(FileSystem::ROOT).is_err());
+    }
+}
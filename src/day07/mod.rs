@@ -1,163 +1,215 @@
-use std::{cell::RefCell, rc::Rc, str::FromStr};
+use std::{fs, path::Path};
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::{event, json, trace::Level};
+
+use self::{
+    filesystem::FileSystem,
+    planner::{Strategy, STRATEGIES},
+    report::Options,
+};
+
+pub mod filesystem;
+pub mod planner;
+pub mod report;
+pub mod transcript;
+
+const DISK_SIZE: usize = 70000000;
+const REQUIRED_SPACE: usize = 30000000;
 
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
-enum Command {
-    ChangeDir(String),
-    List,
+/** Propose what to delete to free up `required` bytes of `capacity`, with one or every strategy */
+pub fn print_plans(
+    input: Vec<String>,
+    capacity: usize,
+    required: usize,
+    strategy: Option<Strategy>,
+) {
+    let file_system = build_tree(&input);
+    let used = file_system.size(FileSystem::ROOT);
+    println!(
+        "Used {} of {}, {} free, {} required",
+        used,
+        capacity,
+        capacity.saturating_sub(used),
+        required
+    );
+
+    for strategy in strategy.map_or(STRATEGIES.to_vec(), |it| vec![it]) {
+        println!();
+
+        let Some(plan) = planner::plan(&file_system, capacity, required, strategy) else {
+            println!("{:?}: not possible", strategy);
+            continue;
+        };
+
+        println!(
+            "{:?}: {} directories, {} bytes{}",
+            strategy,
+            plan.directories.len(),
+            plan.freed,
+            match (plan.optimal, strategy) {
+                (true, _) => "",
+                (false, Strategy::FewestDirectories) => " (may not be the fewest bytes)",
+                (false, _) => " (may not be the best)",
+            }
+        );
+        for directory in plan.directories {
+            println!(
+                "  {:>10} {}",
+                file_system.size(directory),
+                file_system.path(directory)
+            );
+        }
+    }
 }
 
-#[derive(Clone)]
-struct File {
-    name: String,
-    is_directory: bool,
-    children: Vec<Rc<RefCell<File>>>,
-    size: usize,
-    parent: Option<Rc<RefCell<File>>>,
-}
+/** Print the reconstructed filesystem, either drawn as a `tree` or listed like `du` */
+pub fn print_report(input: Vec<String>, style: &str, options: &Options) -> Result<(), String> {
+    let file_system = build_tree(&input);
+    let lines = match style {
+        "tree" => report::tree(&file_system, options),
+        "du" => report::du(&file_system, options),
+        _ => return Err(format!("Unknown report: {}", style)),
+    };
 
-fn parse_command(command: &str) -> Command {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"\$ (\S+)( \S+)?").unwrap();
+    for line in lines {
+        println!("{}", line);
     }
 
-    let caps = RE.captures(command).unwrap();
-    let command_text = caps.get(1).unwrap().as_str();
-    let args = caps.get(2).map(|value| value.as_str());
+    Ok(())
+}
 
-    match command_text {
-        "cd" => Command::ChangeDir(String::from_str(args.unwrap().trim()).unwrap()),
-        _ => Command::List,
+/** List anything in the transcript that had to be guessed at or ignored */
+pub fn check(input: Vec<String>) {
+    let interpretation = transcript::interpret(&input);
+
+    for warning in &interpretation.warnings {
+        println!("{}", warning);
     }
-}
 
-fn count_files(result: &mut Vec<usize>, file: &File) -> usize {
-    let size = if file.is_directory {
-        let mut sum = 0;
-        file.children
-            .iter()
-            .for_each(|it| sum += count_files(result, &it.borrow()));
+    println!(
+        "{} warnings, {} entries",
+        interpretation.warnings.len(),
+        interpretation.file_system.len() - 1
+    );
+}
 
-        sum
+/** Save the tree as `.json`, or as a transcript for anything else */
+pub fn export(input: &[String], path: &Path) -> Result<(), String> {
+    let file_system = build_tree(input);
+    let text = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        file_system.to_json(FileSystem::ROOT)?.pretty()
     } else {
-        file.size
+        transcript::write(&file_system).join("\n")
     };
 
-    if file.is_directory {
-        result.push(size);
-    }
-
-    size
+    fs::write(path, text + "\n").map_err(|err| err.to_string())
 }
 
-fn parse_file(file: &str, parent: Rc<RefCell<File>>) -> File {
-    let mut iter = file.split(' ');
-    let dir_or_size = iter.next().unwrap();
-    let name = iter.next().unwrap();
-
-    if dir_or_size == "dir" {
-        File {
-            name: String::from_str(name).unwrap(),
-            children: vec![],
-            size: 0,
-            is_directory: true,
-            parent: Some(parent),
-        }
+/** Load a tree saved by `export`, and answer the puzzle for it */
+pub fn import(path: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let file_system = if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        FileSystem::from_json(&json::parse(&text)?)?
     } else {
-        File {
-            name: String::from_str(name).unwrap(),
-            children: vec![],
-            size: dir_or_size.parse::<usize>().unwrap(),
-            is_directory: false,
-            parent: Some(parent),
-        }
-    }
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        build_tree(&lines)
+    };
+
+    println!("Part 1: {}", small_directories(&file_system));
+    println!("Part 2: {}", directory_to_delete(&file_system));
+
+    Ok(())
 }
 
-fn build_tree(input: &[String]) -> Rc<RefCell<File>> {
-    let root = File {
-        name: String::from("/"),
-        size: 0,
-        children: vec![],
-        is_directory: true,
-        parent: None,
-    };
-    let root = Rc::new(RefCell::new(root));
-    let mut cwd = root.clone();
-    let mut iter = input.iter();
-
-    while let Some(line) = iter.next() {
-        if line.starts_with('$') {
-            match parse_command(line) {
-                Command::ChangeDir(arg) => match arg.as_str() {
-                    "/" => {
-                        cwd = root.clone();
-                    }
-                    ".." => {
-                        let parent = cwd.borrow().parent.as_ref().cloned().unwrap();
-                        cwd = parent;
-                    }
-                    _ => {
-                        let child = cwd
-                            .borrow()
-                            .children
-                            .iter()
-                            .find(|it| it.borrow().name == arg)
-                            .unwrap()
-                            .clone();
-                        cwd = child;
-                    }
-                },
-                Command::List => {
-                    let ls_iter = iter.clone().take_while(|it| !it.starts_with('$'));
-                    for line in ls_iter {
-                        let file = parse_file(line, cwd.clone());
-                        cwd.borrow_mut().children.push(Rc::new(RefCell::new(file)));
-                        iter.next(); // Advance overall iterator
-                    }
-                }
-            }
+/** Check the tree survives being written out as JSON and as a transcript, and read back in */
+pub fn round_trip(input: Vec<String>) -> Result<(), String> {
+    let file_system = build_tree(&input);
+    let expected = file_system.to_json(FileSystem::ROOT)?;
+
+    let from_json = FileSystem::from_json(&json::parse(&expected.to_string())?)?;
+    let transcript = transcript::write(&file_system);
+    let from_transcript = transcript::interpret(&transcript);
+
+    for (format, actual) in [
+        ("JSON", &from_json),
+        ("Transcript", &from_transcript.file_system),
+    ] {
+        if actual.to_json(FileSystem::ROOT)? == expected {
+            println!("{}: same", format);
+        } else {
+            println!("{}: different", format);
         }
     }
 
-    root
-}
+    println!(
+        "Transcript: {} lines, down from {}, {} warnings",
+        transcript.len(),
+        input.len(),
+        from_transcript.warnings.len()
+    );
 
-fn part1(input: &[String]) -> usize {
-    let root = build_tree(input);
+    Ok(())
+}
 
-    let mut result = 0;
-    let mut small_files = vec![];
-    count_files(&mut small_files, &root.borrow());
+pub fn build_tree(input: &[String]) -> FileSystem {
+    let interpretation = transcript::interpret(input);
 
-    for val in small_files {
-        if val < 100000 {
-            result += val;
-        }
+    for warning in &interpretation.warnings {
+        event!(Level::Debug, "warning", message = warning.to_string());
     }
 
-    result
+    interpretation.file_system
 }
 
-fn part2(input: &[String]) -> usize {
-    let root = build_tree(input);
+/** Total size of every directory, root first */
+fn directory_sizes(file_system: &FileSystem) -> Vec<usize> {
+    file_system
+        .directories()
+        .map(|it| file_system.size(it))
+        .collect()
+}
+
+fn small_directories(file_system: &FileSystem) -> usize {
+    let sizes = directory_sizes(file_system);
+    event!(
+        Level::Debug,
+        "sized",
+        directories = sizes.len(),
+        used_space = file_system.size(FileSystem::ROOT)
+    );
+
+    sizes.into_iter().filter(|size| *size < 100000).sum()
+}
 
-    let mut small_files = vec![];
-    let used_space = count_files(&mut small_files, &root.borrow());
-    small_files.sort();
-    let free_space = 70000000 - used_space;
+fn directory_to_delete(file_system: &FileSystem) -> usize {
+    let mut sizes = directory_sizes(file_system);
+    sizes.sort();
+    let free_space = DISK_SIZE - file_system.size(FileSystem::ROOT);
 
-    for val in small_files {
-        if (free_space + val) >= 30000000 {
+    for val in sizes {
+        if (free_space + val) >= REQUIRED_SPACE {
             return val;
         }
     }
 
     0
 }
+
+pub fn part1(input: &[String]) -> usize {
+    small_directories(&build_tree(input))
+}
+
+pub fn part2(input: &[String]) -> usize {
+    directory_to_delete(&build_tree(input))
+}
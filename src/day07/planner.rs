@@ -0,0 +1,390 @@
+use crate::{event, trace::Level};
+
+use super::filesystem::{FileSystem, NodeId};
+
+/** Which deletions to prefer */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Strategy {
+    /** As few directories as possible, then as few bytes as that allows */
+    FewestDirectories,
+    LeastBytes,
+    /** Only directories with no directories in them, as few bytes as possible */
+    LeavesOnly,
+}
+
+pub const STRATEGIES: [Strategy; 3] = [
+    Strategy::FewestDirectories,
+    Strategy::LeastBytes,
+    Strategy::LeavesOnly,
+];
+
+impl Strategy {
+    pub fn parse(name: &str) -> Result<Strategy, String> {
+        match name {
+            "fewest" => Ok(Strategy::FewestDirectories),
+            "least" => Ok(Strategy::LeastBytes),
+            "leaves" => Ok(Strategy::LeavesOnly),
+            _ => Err(format!("Unknown strategy: {}", name)),
+        }
+    }
+}
+
+/** Directories to delete. None of them are inside another, so nothing is counted twice */
+#[derive(Debug)]
+pub struct Plan {
+    pub directories: Vec<NodeId>,
+    pub freed: usize,
+    /**
+     * False if the search gave up before ruling out anything better. For `FewestDirectories`
+     * that's only ever the bytes, the number of directories is always the fewest there can be
+     */
+    pub optimal: bool,
+}
+
+/** How many branches to try before settling for the best plan found so far */
+const BUDGET: usize = 10_000_000;
+
+/**
+ * Directories to delete so that `required` bytes are free on a disk of `capacity`. `None` if
+ * that can't be done, even by deleting everything the strategy allows
+ */
+pub fn plan(
+    file_system: &FileSystem,
+    capacity: usize,
+    required: usize,
+    strategy: Strategy,
+) -> Option<Plan> {
+    let free = capacity.saturating_sub(file_system.size(FileSystem::ROOT));
+    let Some(need) = required.checked_sub(free).filter(|need| *need > 0) else {
+        return Some(Plan {
+            directories: vec![],
+            freed: 0,
+            optimal: true,
+        });
+    };
+
+    // The root can't be deleted
+    let mut candidates: Vec<NodeId> = file_system
+        .directories()
+        .skip(1)
+        .filter(|it| {
+            strategy != Strategy::LeavesOnly
+                || !file_system
+                    .children(*it)
+                    .any(|child| file_system.is_directory(child))
+        })
+        .collect();
+    candidates.sort_by_key(|it| std::cmp::Reverse(file_system.size(*it)));
+
+    let greedy = greedy(file_system, &candidates, need)?;
+    let max_count = match strategy {
+        Strategy::FewestDirectories => greedy.len(),
+        _ => candidates.len(),
+    };
+
+    let mut search = Search::new(file_system, candidates, need, max_count);
+    search.best_freed = greedy.iter().map(|it| file_system.size(*it)).sum();
+    search.best = greedy;
+    search.run();
+    event!(
+        Level::Debug,
+        "planned",
+        strategy = strategy,
+        visits = search.visits
+    );
+
+    Some(Plan {
+        freed: search.best_freed,
+        directories: search.best,
+        optimal: search.visits <= BUDGET,
+    })
+}
+
+/**
+ * Biggest first, skipping anything inside what's already picked. That's the fewest directories
+ * there can be, as each one picked is the biggest left
+ */
+fn greedy(file_system: &FileSystem, candidates: &[NodeId], need: usize) -> Option<Vec<NodeId>> {
+    let mut picked: Vec<NodeId> = vec![];
+    let mut is_picked = vec![false; file_system.len()];
+    let mut freed = 0;
+
+    for candidate in candidates {
+        if freed >= need {
+            break;
+        }
+
+        if !is_inside(file_system, *candidate, &is_picked) {
+            picked.push(*candidate);
+            is_picked[*candidate] = true;
+            freed += file_system.size(*candidate);
+        }
+    }
+
+    (freed >= need).then_some(picked)
+}
+
+/** Whether `id` is inside any of the directories marked in `marked`, which is indexed by id */
+fn is_inside(file_system: &FileSystem, id: NodeId, marked: &[bool]) -> bool {
+    file_system
+        .ancestors(id)
+        .skip(1)
+        .any(|ancestor| marked[ancestor])
+}
+
+/** Branch and bound over the candidates, biggest first, for the fewest bytes that are enough */
+struct Search<'a> {
+    file_system: &'a FileSystem,
+    candidates: Vec<NodeId>,
+    /** Total size of each candidate onwards, to give up on branches that can't free enough */
+    remaining: Vec<usize>,
+    need: usize,
+    max_count: usize,
+    chosen: Vec<NodeId>,
+    /** Indexed by id, so checking against `chosen` doesn't mean looking through it */
+    is_chosen: Vec<bool>,
+    best: Vec<NodeId>,
+    best_freed: usize,
+    visits: usize,
+}
+
+impl<'a> Search<'a> {
+    fn new(
+        file_system: &'a FileSystem,
+        candidates: Vec<NodeId>,
+        need: usize,
+        max_count: usize,
+    ) -> Search<'a> {
+        let mut remaining = vec![0; candidates.len() + 1];
+        for i in (0..candidates.len()).rev() {
+            remaining[i] = remaining[i + 1] + file_system.size(candidates[i]);
+        }
+
+        Search {
+            file_system,
+            candidates,
+            remaining,
+            need,
+            max_count,
+            chosen: vec![],
+            is_chosen: vec![false; file_system.len()],
+            best: vec![],
+            best_freed: usize::MAX,
+            visits: 0,
+        }
+    }
+
+    /** Depth first, on a stack of its own, as recursing once per candidate could overflow */
+    fn run(&mut self) {
+        let mut steps = vec![Step::Enter { i: 0, freed: 0 }];
+
+        while let Some(step) = steps.pop() {
+            let (i, freed) = match step {
+                Step::Enter { i, freed } => (i, freed),
+                Step::Leave(candidate) => {
+                    self.is_chosen[candidate] = false;
+                    self.chosen.pop();
+                    continue;
+                }
+            };
+
+            self.visits += 1;
+            // Nothing can beat freeing exactly what's needed
+            if self.visits > BUDGET || self.best_freed == self.need {
+                return;
+            }
+
+            if freed >= self.need {
+                if freed < self.best_freed {
+                    self.best_freed = freed;
+                    self.best = self.chosen.clone();
+                }
+                continue;
+            }
+
+            if i == self.candidates.len()
+                || self.chosen.len() == self.max_count
+                || freed + self.remaining[i] < self.need
+            {
+                continue;
+            }
+
+            let candidate = self.candidates[i];
+            let size = self.file_system.size(candidate);
+
+            // Pushed in reverse, so leaving the candidate out is tried after putting it in
+            steps.push(Step::Enter { i: i + 1, freed });
+
+            if freed + size < self.best_freed
+                && !is_inside(self.file_system, candidate, &self.is_chosen)
+            {
+                self.chosen.push(candidate);
+                self.is_chosen[candidate] = true;
+                steps.push(Step::Leave(candidate));
+                steps.push(Step::Enter {
+                    i: i + 1,
+                    freed: freed + size,
+                });
+            }
+        }
+    }
+}
+
+enum Step {
+    /** Decide on candidate `i`, with `freed` bytes chosen so far */
+    Enter { i: usize, freed: usize },
+    /** Undo choosing a candidate, once everything after it has been tried */
+    Leave(NodeId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_inside, plan, Strategy, STRATEGIES};
+    use crate::day07::filesystem::{FileSystem, NodeId};
+
+    /** The puzzle's example */
+    fn example() -> FileSystem {
+        let mut file_system = FileSystem::new();
+        let root = FileSystem::ROOT;
+        let a = file_system.add_directory(root, "a").unwrap();
+        let e = file_system.add_directory(a, "e").unwrap();
+        file_system.add_file(e, "i", 584).unwrap();
+        for (name, size) in [("f", 29116), ("g", 2557), ("h.lst", 62596)] {
+            file_system.add_file(a, name, size).unwrap();
+        }
+        file_system.add_file(root, "b.txt", 14848514).unwrap();
+        file_system.add_file(root, "c.dat", 8504156).unwrap();
+        let d = file_system.add_directory(root, "d").unwrap();
+        for (name, size) in [
+            ("j", 4060174),
+            ("d.log", 8033020),
+            ("d.ext", 5626152),
+            ("k", 7214296),
+        ] {
+            file_system.add_file(d, name, size).unwrap();
+        }
+
+        file_system
+    }
+
+    /** A few levels of directories, with files of made up sizes */
+    fn generated(seed: u64) -> FileSystem {
+        let mut state = seed;
+        let mut next = move |limit: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % limit
+        };
+
+        let mut file_system = FileSystem::new();
+        let mut directories = vec![FileSystem::ROOT];
+        for i in 0..10 {
+            let parent = directories[next(directories.len() as u64) as usize];
+            let directory = file_system
+                .add_directory(parent, &format!("d{}", i))
+                .unwrap();
+            directories.push(directory);
+            file_system
+                .add_file(directory, "f", 1 + next(1000) as usize)
+                .unwrap();
+        }
+
+        file_system
+    }
+
+    /** Fewest bytes, then fewest directories, from trying every set of candidates */
+    fn brute_force(
+        file_system: &FileSystem,
+        need: usize,
+        strategy: Strategy,
+    ) -> Option<(usize, usize)> {
+        let candidates: Vec<NodeId> = file_system
+            .directories()
+            .skip(1)
+            .filter(|it| {
+                strategy != Strategy::LeavesOnly
+                    || !file_system
+                        .children(*it)
+                        .any(|child| file_system.is_directory(child))
+            })
+            .collect();
+        let mut best: Option<(usize, usize)> = None;
+
+        for set in 0..1u32 << candidates.len() {
+            let mut marked = vec![false; file_system.len()];
+            let chosen: Vec<NodeId> = (0..candidates.len())
+                .filter(|i| set & (1 << i) != 0)
+                .map(|i| candidates[i])
+                .collect();
+            for id in &chosen {
+                marked[*id] = true;
+            }
+            if chosen.iter().any(|id| is_inside(file_system, *id, &marked)) {
+                continue;
+            }
+
+            let freed: usize = chosen.iter().map(|id| file_system.size(*id)).sum();
+            let score = match strategy {
+                Strategy::FewestDirectories => (chosen.len(), freed),
+                _ => (freed, chosen.len()),
+            };
+            if freed >= need && best.is_none_or(|best| score < best) {
+                best = Some(score);
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn example_deletes_d() {
+        let file_system = example();
+
+        for strategy in STRATEGIES {
+            let plan = plan(&file_system, 70000000, 30000000, strategy).unwrap();
+
+            assert_eq!(plan.freed, 24933642, "{:?}", strategy);
+            assert_eq!(plan.directories, [file_system.lookup("/d").unwrap()]);
+            assert!(plan.optimal);
+        }
+    }
+
+    #[test]
+    fn nothing_to_do_or_nothing_enough() {
+        let file_system = example();
+
+        let plan = plan(&file_system, 100000000, 30000000, Strategy::LeastBytes).unwrap();
+        assert!(plan.directories.is_empty());
+
+        assert!(super::plan(&file_system, 48381166, 48381166, Strategy::LeastBytes).is_none());
+    }
+
+    #[test]
+    fn plans_match_trying_everything() {
+        for seed in 0..20 {
+            let file_system = generated(seed);
+            let used = file_system.size(FileSystem::ROOT);
+
+            for need in [used / 4, used / 2, used * 3 / 4] {
+                for strategy in STRATEGIES {
+                    let expected = brute_force(&file_system, need, strategy);
+                    let plan = plan(&file_system, used, need, strategy);
+                    let actual = plan.map(|plan| match strategy {
+                        Strategy::FewestDirectories => (plan.directories.len(), plan.freed),
+                        _ => (plan.freed, plan.directories.len()),
+                    });
+
+                    assert_eq!(
+                        actual.map(|it| it.0),
+                        expected.map(|it| it.0),
+                        "seed {}, need {}, {:?}",
+                        seed,
+                        need,
+                        strategy
+                    );
+                }
+            }
+        }
+    }
+}
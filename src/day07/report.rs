@@ -0,0 +1,306 @@
+use super::filesystem::{FileSystem, NodeId};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sort {
+    /** As listed in the transcript */
+    Listed,
+    Name,
+    /** Biggest first */
+    Size,
+}
+
+impl Sort {
+    pub fn parse(name: &str) -> Result<Sort, String> {
+        match name {
+            "listed" => Ok(Sort::Listed),
+            "name" => Ok(Sort::Name),
+            "size" => Ok(Sort::Size),
+            _ => Err(format!("Unknown sort: {}", name)),
+        }
+    }
+}
+
+/** What to show, and in what order */
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    /** How many levels below the root to go, if limited */
+    pub depth: Option<usize>,
+    pub sort: Sort,
+    /** Leave out directories smaller than this, along with what's in them */
+    pub min_size: usize,
+    pub directories_only: bool,
+    /** Sizes like `du -h`, rather than in bytes */
+    pub human: bool,
+}
+
+impl Options {
+    fn shows(&self, file_system: &FileSystem, id: NodeId) -> bool {
+        if file_system.is_directory(id) {
+            file_system.size(id) >= self.min_size
+        } else {
+            !self.directories_only
+        }
+    }
+
+    fn size(&self, bytes: usize) -> String {
+        if self.human {
+            human(bytes)
+        } else {
+            bytes.to_string()
+        }
+    }
+
+    /** The children of `id` worth showing, in order */
+    fn children(&self, file_system: &FileSystem, id: NodeId) -> Vec<NodeId> {
+        let mut children: Vec<NodeId> = file_system
+            .children(id)
+            .filter(|child| self.shows(file_system, *child))
+            .collect();
+
+        match self.sort {
+            Sort::Listed => {}
+            Sort::Name => {
+                children.sort_by(|a, b| file_system.node(*a).name.cmp(&file_system.node(*b).name))
+            }
+            Sort::Size => children.sort_by_key(|it| std::cmp::Reverse(file_system.size(*it))),
+        }
+
+        children
+    }
+}
+
+/** Like `du -h`: 1024 based, one decimal place below 10 */
+pub fn human(bytes: usize) -> String {
+    const UNITS: [&str; 6] = ["", "K", "M", "G", "T", "P"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        bytes.to_string()
+    } else if size < 10.0 {
+        format!("{:.1}{}", size, UNITS[unit])
+    } else {
+        format!("{:.0}{}", size, UNITS[unit])
+    }
+}
+
+/** The tree drawn like `tree`, sizes alongside */
+pub fn tree(file_system: &FileSystem, options: &Options) -> Vec<String> {
+    let mut lines = vec![format!(
+        "/ ({})",
+        options.size(file_system.size(FileSystem::ROOT))
+    )];
+
+    // One entry per directory being drawn: the children still to come, and the prefix they get.
+    // Kept on the heap, so however deep the tree goes, the call stack doesn't
+    let mut open = vec![];
+    if options.depth.is_none_or(|limit| limit > 0) {
+        let children = options.children(file_system, FileSystem::ROOT);
+        open.push((children.into_iter().peekable(), String::new()));
+    }
+
+    loop {
+        let depth = open.len();
+        let Some((children, prefix)) = open.last_mut() else {
+            break;
+        };
+        let Some(child) = children.next() else {
+            open.pop();
+            continue;
+        };
+        let last = children.peek().is_none();
+        let node = file_system.node(child);
+        let suffix = if file_system.is_directory(child) {
+            "/"
+        } else {
+            ""
+        };
+
+        lines.push(format!(
+            "{}{}{}{} ({})",
+            prefix,
+            if last { "└── " } else { "├── " },
+            node.name,
+            suffix,
+            options.size(file_system.size(child))
+        ));
+
+        if file_system.is_directory(child) && options.depth.is_none_or(|limit| depth < limit) {
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            let children = options.children(file_system, child);
+            open.push((children.into_iter().peekable(), prefix));
+        }
+    }
+
+    lines
+}
+
+/**
+ * Every directory's total and path, like `du`. Unless sorted, directories come after their
+ * contents
+ */
+pub fn du(file_system: &FileSystem, options: &Options) -> Vec<String> {
+    let mut directories = collect(file_system, options);
+
+    match options.sort {
+        Sort::Listed => {}
+        Sort::Name => directories.sort_by_key(|it| file_system.path(*it)),
+        Sort::Size => directories.sort_by_key(|it| std::cmp::Reverse(file_system.size(*it))),
+    }
+
+    directories
+        .into_iter()
+        .map(|it| {
+            format!(
+                "{:<10}{}",
+                options.size(file_system.size(it)),
+                file_system.path(it)
+            )
+        })
+        .collect()
+}
+
+enum Visit {
+    /** Look at a directory `depth` levels down, and queue up what's in it */
+    Enter { id: NodeId, depth: usize },
+    /** Everything in the directory has been seen to */
+    Leave(NodeId),
+}
+
+/** Directories worth listing, each after its contents */
+fn collect(file_system: &FileSystem, options: &Options) -> Vec<NodeId> {
+    let mut directories = vec![];
+    let mut stack = vec![Visit::Enter {
+        id: FileSystem::ROOT,
+        depth: 0,
+    }];
+
+    while let Some(visit) = stack.pop() {
+        let (id, depth) = match visit {
+            Visit::Enter { id, depth } => (id, depth),
+            Visit::Leave(id) => {
+                directories.push(id);
+                continue;
+            }
+        };
+
+        if file_system.size(id) < options.min_size {
+            continue;
+        }
+
+        stack.push(Visit::Leave(id));
+
+        if options.depth.is_none_or(|limit| depth < limit) {
+            let children: Vec<NodeId> = file_system
+                .children(id)
+                .filter(|child| file_system.is_directory(*child))
+                .collect();
+
+            // Reversed, so they come off the stack in order
+            for child in children.into_iter().rev() {
+                stack.push(Visit::Enter {
+                    id: child,
+                    depth: depth + 1,
+                });
+            }
+        }
+    }
+
+    directories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{du, tree, Options, Sort};
+    use crate::day07::filesystem::FileSystem;
+
+    const OPTIONS: Options = Options {
+        depth: None,
+        sort: Sort::Listed,
+        min_size: 0,
+        directories_only: false,
+        human: false,
+    };
+
+    fn example() -> FileSystem {
+        let mut file_system = FileSystem::new();
+        let a = file_system.add_directory(FileSystem::ROOT, "a").unwrap();
+        let e = file_system.add_directory(a, "e").unwrap();
+        file_system.add_file(e, "i", 584).unwrap();
+        file_system.add_file(a, "f", 29116).unwrap();
+        file_system
+            .add_file(FileSystem::ROOT, "b.txt", 14848514)
+            .unwrap();
+        file_system.add_directory(FileSystem::ROOT, "d").unwrap();
+
+        file_system
+    }
+
+    #[test]
+    fn draws_the_tree() {
+        assert_eq!(
+            tree(&example(), &OPTIONS),
+            [
+                "/ (14878214)",
+                "├── a/ (29700)",
+                "│   ├── e/ (584)",
+                "│   │   └── i (584)",
+                "│   └── f (29116)",
+                "├── b.txt (14848514)",
+                "└── d/ (0)",
+            ]
+        );
+
+        let options = Options {
+            depth: Some(1),
+            sort: Sort::Size,
+            ..OPTIONS
+        };
+        assert_eq!(
+            tree(&example(), &options),
+            [
+                "/ (14878214)",
+                "├── b.txt (14848514)",
+                "├── a/ (29700)",
+                "└── d/ (0)"
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_directories_after_their_contents() {
+        assert_eq!(
+            du(&example(), &OPTIONS),
+            [
+                "584       /a/e",
+                "29700     /a",
+                "0         /d",
+                "14878214  /"
+            ]
+        );
+
+        let options = Options {
+            depth: Some(1),
+            min_size: 1,
+            ..OPTIONS
+        };
+        assert_eq!(du(&example(), &options), ["29700     /a", "14878214  /"]);
+    }
+
+    #[test]
+    fn deep_trees_fit_on_the_stack() {
+        let mut file_system = FileSystem::new();
+        let mut directory = FileSystem::ROOT;
+        for _ in 0..5_000 {
+            directory = file_system.add_directory(directory, "a").unwrap();
+        }
+
+        assert_eq!(tree(&file_system, &OPTIONS).len(), 5_001);
+        assert_eq!(du(&file_system, &OPTIONS).len(), 5_001);
+    }
+}
@@ -0,0 +1,397 @@
+use std::fmt::{self, Display};
+
+use super::filesystem::{is_valid_name, Conflict, FileSystem, NodeId};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    /** `cd` somewhere that hadn't been listed. It's created, so what follows isn't lost */
+    UnknownDirectory(String),
+    MissingArgument(String),
+    /** `ls` somewhere other than the current directory. Its output is ignored */
+    UnexpectedArgument(String),
+    /** Anything but `cd` and `ls`. Its output is ignored */
+    UnknownCommand(String),
+    /** Output with no `ls` before it */
+    UnexpectedOutput(String),
+    /** Not `dir <name>` or `<size> <name>`, or a name `cd` couldn't get to */
+    MalformedEntry(String),
+    /**
+     * An entry listed again, but differently, or `cd` into what was listed as a file. The first
+     * listing is kept, and after a bad `cd`, listings are ignored until a `cd /...`
+     */
+    Contradiction {
+        path: String,
+        entry: String,
+    },
+}
+
+/** Something in the transcript that had to be guessed at or left out */
+#[derive(Debug, PartialEq, Eq)]
+pub struct Warning {
+    /** From 1 */
+    pub line: usize,
+    pub kind: WarningKind,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: ", self.line)?;
+
+        match &self.kind {
+            WarningKind::UnknownDirectory(path) => {
+                write!(f, "'{}' wasn't listed before cd, assuming it exists", path)
+            }
+            WarningKind::MissingArgument(command) => write!(f, "'{}' needs an argument", command),
+            WarningKind::UnexpectedArgument(command) => {
+                write!(
+                    f,
+                    "'{}' only lists the current directory, ignoring its output",
+                    command
+                )
+            }
+            WarningKind::UnknownCommand(command) => {
+                write!(f, "unknown command '{}', ignoring its output", command)
+            }
+            WarningKind::UnexpectedOutput(text) => write!(f, "'{}' isn't the output of ls", text),
+            WarningKind::MalformedEntry(text) => write!(f, "'{}' isn't a listing entry", text),
+            WarningKind::Contradiction { path, entry } => {
+                write!(f, "'{}' was already listed differently in {}", entry, path)
+            }
+        }
+    }
+}
+
+/** The filesystem a transcript describes, and what was wrong with it */
+pub struct Interpretation {
+    pub file_system: FileSystem,
+    pub warnings: Vec<Warning>,
+}
+
+/** Where output lines go */
+enum Output {
+    /** Before the first command, or after a `cd` */
+    None,
+    /** Entries in this directory */
+    Listing(NodeId),
+    Ignored,
+}
+
+/** Replay a transcript of `cd` and `ls`, carrying on past anything it doesn't understand */
+pub fn interpret(input: &[String]) -> Interpretation {
+    let mut file_system = FileSystem::new();
+    let mut warnings = vec![];
+    // `None` once a `cd` couldn't be followed, so nothing is listed in the wrong place
+    let mut cwd = Some(FileSystem::ROOT);
+    let mut output = Output::None;
+
+    for (i, line) in input.iter().enumerate() {
+        let mut warn = |kind| warnings.push(Warning { line: i + 1, kind });
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(command) = line.strip_prefix('$') {
+            let mut words = command.split_whitespace();
+
+            output = match (words.next(), words.next()) {
+                (Some("cd"), Some(path)) => {
+                    cwd = change_directory(&mut file_system, cwd, path, &mut warn);
+                    Output::None
+                }
+                (Some("cd"), None) => {
+                    warn(WarningKind::MissingArgument(String::from("cd")));
+                    Output::None
+                }
+                (Some("ls"), None) => cwd.map_or(Output::Ignored, Output::Listing),
+                (Some("ls"), Some(_)) => {
+                    warn(WarningKind::UnexpectedArgument(command.trim().to_string()));
+                    Output::Ignored
+                }
+                _ => {
+                    warn(WarningKind::UnknownCommand(command.trim().to_string()));
+                    Output::Ignored
+                }
+            };
+            continue;
+        }
+
+        match output {
+            Output::Listing(directory) => list(&mut file_system, directory, line, &mut warn),
+            Output::Ignored => {}
+            Output::None => warn(WarningKind::UnexpectedOutput(line.to_string())),
+        }
+    }
+
+    Interpretation {
+        file_system,
+        warnings,
+    }
+}
+
+/**
+ * Where `cd path` ends up. Missing directories along the way are created, but a file in the
+ * way can't be gone into, so that's `None`. From `None`, only an absolute path gets anywhere
+ */
+fn change_directory(
+    file_system: &mut FileSystem,
+    cwd: Option<NodeId>,
+    path: &str,
+    warn: &mut impl FnMut(WarningKind),
+) -> Option<NodeId> {
+    let start = if path.starts_with('/') {
+        FileSystem::ROOT
+    } else {
+        cwd?
+    };
+
+    if let Some(target) = file_system
+        .resolve(start, path)
+        .filter(|it| file_system.is_directory(*it))
+    {
+        return Some(target);
+    }
+
+    let mut current = start;
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        current = match segment {
+            "." => current,
+            ".." => file_system.parent(current).unwrap_or(FileSystem::ROOT),
+            name => match file_system.add_directory(current, name) {
+                Ok(directory) => directory,
+                Err(Conflict { .. }) => {
+                    warn(WarningKind::Contradiction {
+                        path: file_system.path(current),
+                        entry: format!("dir {}", name),
+                    });
+                    return None;
+                }
+            },
+        };
+    }
+
+    warn(WarningKind::UnknownDirectory(path.to_string()));
+
+    Some(current)
+}
+
+fn list(file_system: &mut FileSystem, cwd: NodeId, line: &str, warn: &mut impl FnMut(WarningKind)) {
+    let Some((dir_or_size, name)) = line.split_once(' ').filter(|(_, name)| is_valid_name(name))
+    else {
+        warn(WarningKind::MalformedEntry(line.to_string()));
+        return;
+    };
+
+    let added = match (dir_or_size, dir_or_size.parse::<usize>()) {
+        ("dir", _) => file_system.add_directory(cwd, name),
+        (_, Ok(size)) => file_system.add_file(cwd, name, size),
+        _ => {
+            warn(WarningKind::MalformedEntry(line.to_string()));
+            return;
+        }
+    };
+
+    if let Err(Conflict { .. }) = added {
+        warn(WarningKind::Contradiction {
+            path: file_system.path(cwd),
+            entry: line.to_string(),
+        });
+    }
+}
+
+/**
+ * A transcript `interpret` turns back into `file_system`: one `ls` per directory that has
+ * anything in it, with `cd`s to get between them
+ */
+pub fn write(file_system: &FileSystem) -> Vec<String> {
+    let mut lines = vec![String::from("$ cd /")];
+    write_directory(file_system, FileSystem::ROOT, &mut lines);
+
+    // Nothing needs doing after the last listing
+    while lines.last().is_some_and(|line| line == "$ cd ..") {
+        lines.pop();
+    }
+
+    lines
+}
+
+enum Step {
+    /** `ls` a directory, then queue up going into whichever of its directories have contents */
+    List(NodeId),
+    Enter(NodeId),
+    Leave,
+}
+
+fn write_directory(file_system: &FileSystem, id: NodeId, lines: &mut Vec<String>) {
+    let mut stack = vec![Step::List(id)];
+
+    while let Some(step) = stack.pop() {
+        let id = match step {
+            Step::List(id) => id,
+            Step::Enter(id) => {
+                lines.push(format!("$ cd {}", file_system.node(id).name));
+                stack.push(Step::List(id));
+                continue;
+            }
+            Step::Leave => {
+                lines.push(String::from("$ cd .."));
+                continue;
+            }
+        };
+
+        lines.push(String::from("$ ls"));
+
+        for child in file_system.children(id) {
+            let name = &file_system.node(child).name;
+
+            if file_system.is_directory(child) {
+                lines.push(format!("dir {}", name));
+            } else {
+                lines.push(format!("{} {}", file_system.size(child), name));
+            }
+        }
+
+        let visits: Vec<NodeId> = file_system
+            .children(id)
+            .filter(|child| {
+                file_system.is_directory(*child) && file_system.children(*child).next().is_some()
+            })
+            .collect();
+
+        // Reversed, so they come off the stack in order
+        for child in visits.into_iter().rev() {
+            stack.push(Step::Leave);
+            stack.push(Step::Enter(child));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{interpret, write, WarningKind};
+    use crate::day07::filesystem::FileSystem;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(String::from).collect()
+    }
+
+    fn kinds(text: &str) -> (FileSystem, Vec<WarningKind>) {
+        let interpretation = interpret(&lines(text));
+        let kinds = interpretation
+            .warnings
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect();
+
+        (interpretation.file_system, kinds)
+    }
+
+    #[test]
+    fn cd_into_a_file_loses_track_until_an_absolute_cd() {
+        let (file_system, warnings) = kinds(
+            "$ cd /\n$ ls\n10 a\n$ cd a\n$ ls\n20 b\n$ cd c\n$ ls\n30 d\n$ cd /c\n$ ls\n40 e",
+        );
+
+        assert_eq!(
+            warnings,
+            [
+                WarningKind::Contradiction {
+                    path: String::from("/"),
+                    entry: String::from("dir a"),
+                },
+                WarningKind::UnknownDirectory(String::from("/c")),
+            ]
+        );
+        assert_eq!(file_system.lookup("/b"), None);
+        assert_eq!(file_system.lookup("/d"), None);
+        assert!(file_system.lookup("/c/e").is_some());
+        assert_eq!(file_system.size(FileSystem::ROOT), 50);
+    }
+
+    #[test]
+    fn ls_with_arguments_is_ignored() {
+        let (file_system, warnings) = kinds("$ cd /\n$ ls elsewhere\n10 a\n$ ls\n20 b");
+
+        assert_eq!(
+            warnings,
+            [WarningKind::UnexpectedArgument(String::from(
+                "ls elsewhere"
+            ))]
+        );
+        assert_eq!(file_system.lookup("/a"), None);
+        assert_eq!(file_system.size(FileSystem::ROOT), 20);
+    }
+
+    #[test]
+    fn unlisted_directories_are_assumed() {
+        let (file_system, warnings) = kinds("$ cd /x/y\n$ ls\n5 z\n$ cd ../..\n$ ls\ndir x");
+
+        assert_eq!(
+            warnings,
+            [WarningKind::UnknownDirectory(String::from("/x/y"))]
+        );
+        assert_eq!(file_system.size(file_system.lookup("/x").unwrap()), 5);
+    }
+
+    #[test]
+    fn written_transcripts_round_trip() {
+        let (file_system, warnings) = kinds(
+            "$ cd /\n$ ls\ndir a\n14848514 b.txt\ndir d\n$ cd a\n$ ls\ndir e\n29116 f\n\
+             $ cd e\n$ ls\n584 i\n$ cd /d\n$ ls",
+        );
+        assert_eq!(warnings, []);
+
+        let transcript = write(&file_system);
+        let read = interpret(&transcript);
+
+        assert_eq!(
+            transcript,
+            [
+                "$ cd /",
+                "$ ls",
+                "dir a",
+                "14848514 b.txt",
+                "dir d",
+                "$ cd a",
+                "$ ls",
+                "dir e",
+                "29116 f",
+                "$ cd e",
+                "$ ls",
+                "584 i",
+            ]
+        );
+        assert_eq!(read.warnings, []);
+        assert_eq!(
+            read.file_system.to_json(FileSystem::ROOT),
+            file_system.to_json(FileSystem::ROOT)
+        );
+    }
+
+    #[test]
+    fn names_cd_couldnt_reach_are_malformed() {
+        let (file_system, warnings) = kinds("$ ls\ndir ..\n1 two words\n2 .");
+
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings
+            .iter()
+            .all(|warning| matches!(warning, WarningKind::MalformedEntry(_))));
+        assert_eq!(file_system.len(), 1);
+    }
+
+    #[test]
+    fn deep_transcripts_fit_on_the_stack() {
+        let depth = 5_000;
+        let mut text = String::from("$ cd /\n");
+        for _ in 0..depth {
+            text.push_str("$ ls\ndir a\n$ cd a\n");
+        }
+        text.push_str("$ ls\n1 f");
+
+        let (file_system, _) = kinds(&text);
+        let transcript = write(&file_system);
+
+        assert_eq!(transcript.len(), 1 + 3 * depth + 2);
+        assert_eq!(interpret(&transcript).file_system.len(), depth + 2);
+    }
+}
@@ -1,8 +1,29 @@
+use crate::{
+    event,
+    image::{Image, Rgb},
+    trace::Level,
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
+/** Heatmap of tree heights, one pixel per tree */
+pub fn image(input: &[String]) -> Image {
+    let grid = parse_input(input);
+    let mut image = Image::new(grid[0].len() as u32, grid.len() as u32, Rgb::BLACK);
+
+    for (row, heights) in grid.iter().enumerate() {
+        for (col, height) in heights.iter().enumerate() {
+            let color = Rgb(20, 40, 20).lerp(Rgb(170, 255, 120), *height as f64 / 9.0);
+            image.set(col as u32, row as u32, color);
+        }
+    }
+
+    image
+}
+
 /** Grid of trees, indexed by `row` then `col` */
 type TreeGrid = Vec<Vec<u8>>;
 
@@ -17,8 +38,8 @@ fn is_visible(grid: &TreeGrid, row: usize, col: usize) -> bool {
     let mut visible = true;
 
     // Left Edge
-    for c in 0..col {
-        if grid[row][c] >= height {
+    for &tree in grid[row][..col].iter() {
+        if tree >= height {
             visible = false;
             break;
         }
@@ -30,8 +51,8 @@ fn is_visible(grid: &TreeGrid, row: usize, col: usize) -> bool {
 
     visible = true;
     // Right Edge
-    for c in (col + 1)..grid[0].len() {
-        if grid[row][c] >= height {
+    for &tree in grid[row][(col + 1)..].iter() {
+        if tree >= height {
             visible = false;
             break;
         }
@@ -44,8 +65,8 @@ fn is_visible(grid: &TreeGrid, row: usize, col: usize) -> bool {
     visible = true;
 
     // Top Edge
-    for r in 0..row {
-        if grid[r][col] >= height {
+    for tree_row in grid[..row].iter() {
+        if tree_row[col] >= height {
             visible = false;
             break;
         }
@@ -58,8 +79,8 @@ fn is_visible(grid: &TreeGrid, row: usize, col: usize) -> bool {
     visible = true;
 
     // Bottom Edge
-    for r in (row + 1)..grid.len() {
-        if grid[r][col] >= height {
+    for tree_row in grid[(row + 1)..].iter() {
+        if tree_row[col] >= height {
             visible = false;
             break;
         }
@@ -77,45 +98,45 @@ fn calculate_scenic_score(grid: &TreeGrid, row: usize, col: usize) -> usize {
     let mut down = 0;
 
     // Left
-    for c in (0..col).rev() {
-        if grid[row][c] <= height {
+    for &tree in grid[row][..col].iter().rev() {
+        if tree <= height {
             left += 1
         }
 
-        if grid[row][c] >= height {
+        if tree >= height {
             break;
         }
     }
 
     // Right
-    for c in (col + 1)..grid[0].len() {
-        if grid[row][c] <= height {
+    for &tree in grid[row][(col + 1)..].iter() {
+        if tree <= height {
             right += 1;
         }
 
-        if grid[row][c] >= height {
+        if tree >= height {
             break;
         }
     }
 
     // Up
-    for r in (0..row).rev() {
-        if grid[r][col] <= height {
+    for tree_row in grid[..row].iter().rev() {
+        if tree_row[col] <= height {
             up += 1;
         }
 
-        if grid[r][col] >= height {
+        if tree_row[col] >= height {
             break;
         }
     }
 
     // Down
-    for r in (row + 1)..grid.len() {
-        if grid[r][col] <= height {
+    for tree_row in grid[(row + 1)..].iter() {
+        if tree_row[col] <= height {
             down += 1;
         }
 
-        if grid[r][col] >= height {
+        if tree_row[col] >= height {
             break;
         }
     }
@@ -135,10 +156,17 @@ fn parse_input(input: &[String]) -> TreeGrid {
         grid.push(row);
     }
 
+    event!(
+        Level::Debug,
+        "parsed",
+        rows = grid.len(),
+        cols = grid.first().map_or(0, |row| row.len())
+    );
+
     grid
 }
 
-fn part1(input: &[String]) -> usize {
+pub fn part1(input: &[String]) -> usize {
     let grid = parse_input(input);
     let mut result = 0;
     let rows = grid.len();
@@ -155,7 +183,7 @@ fn part1(input: &[String]) -> usize {
     result
 }
 
-fn part2(input: &[String]) -> usize {
+pub fn part2(input: &[String]) -> usize {
     let grid = parse_input(input);
     let mut result = 0;
     let rows = grid.len();
@@ -1,10 +1,26 @@
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
+
+use crate::{
+    animation::{Animator, Scene, Viewport},
+    event,
+    simulation::{self, Simulation},
+    trace::Level,
+};
 
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
+/** Watch the rope's knots follow the head */
+pub fn animate(input: Vec<String>, num_knots: usize, mut animator: Animator) {
+    let mut rope = Rope::new(num_knots);
+    rope.load(&parse_input(&input));
+
+    simulation::run(&mut rope, &mut [&mut animator]);
+}
+
+#[derive(Clone, Copy, Debug)]
 enum Direction {
     Up,
     Down,
@@ -12,11 +28,14 @@ enum Direction {
     Right,
 }
 
-type Position = (i32, i32);
+pub type Position = (i32, i32);
 
-struct Rope {
+/** A rope whose head follows the puzzle's moves, one space per step */
+pub struct Rope {
     knots: Vec<Position>,
     tail_positions: HashSet<Position>,
+    /** Single-space head moves still to be made */
+    moves: VecDeque<Direction>,
 }
 
 impl Rope {
@@ -24,58 +43,132 @@ impl Rope {
         Rope {
             knots: vec![(0, 0); num_knots],
             tail_positions: HashSet::from_iter([(0, 0)]),
+            moves: VecDeque::new(),
         }
     }
 
-    fn move_head(&mut self, direction: Direction, distance: usize) {
-        for _ in 0..distance {
-            let head = &mut self.knots[0];
+    /** A rope of `num_knots` knots, with the moves in `input` still to make */
+    pub fn from_input(input: &[String], num_knots: usize) -> Rope {
+        let mut rope = Rope::new(num_knots);
+        rope.load(&parse_input(input));
 
-            match direction {
-                Direction::Up => {
-                    head.1 += 1;
-                }
-                Direction::Down => {
-                    head.1 -= 1;
-                }
-                Direction::Left => {
-                    head.0 -= 1;
-                }
-                Direction::Right => {
-                    head.0 += 1;
-                }
+        rope
+    }
+
+    /** How many positions the tail has visited so far */
+    pub fn tail_visits(&self) -> usize {
+        self.tail_positions.len()
+    }
+
+    fn load(&mut self, steps: &[(Direction, usize)]) {
+        for (direction, distance) in steps {
+            for _ in 0..*distance {
+                self.moves.push_back(*direction);
             }
+        }
+    }
 
-            for i in 1..self.knots.len() {
-                let head = self.knots[i - 1];
-                let tail = &mut self.knots[i];
+    fn move_head(&mut self, direction: Direction) {
+        let head = &mut self.knots[0];
 
-                let dx = head.0 - tail.0;
-                let dy = head.1 - tail.1;
+        match direction {
+            Direction::Up => {
+                head.1 += 1;
+            }
+            Direction::Down => {
+                head.1 -= 1;
+            }
+            Direction::Left => {
+                head.0 -= 1;
+            }
+            Direction::Right => {
+                head.0 += 1;
+            }
+        }
 
-                // Drifted horizontally
-                if dx.abs() == 2 {
-                    // Diagonal
-                    if dy.abs() > 0 {
-                        tail.1 += if dy > 0 { 1 } else { -1 };
-                    }
+        for i in 1..self.knots.len() {
+            let head = self.knots[i - 1];
+            let tail = &mut self.knots[i];
 
-                    tail.0 += if dx > 0 { 1 } else { -1 };
-                }
-                // Drifted vertically
-                else if dy.abs() == 2 {
-                    // Diagonal
-                    if dx.abs() > 0 {
-                        tail.0 += if dx > 0 { 1 } else { -1 };
-                    }
+            let dx = head.0 - tail.0;
+            let dy = head.1 - tail.1;
 
+            // Drifted horizontally
+            if dx.abs() == 2 {
+                // Diagonal
+                if dy.abs() > 0 {
                     tail.1 += if dy > 0 { 1 } else { -1 };
                 }
+
+                tail.0 += if dx > 0 { 1 } else { -1 };
             }
+            // Drifted vertically
+            else if dy.abs() == 2 {
+                // Diagonal
+                if dx.abs() > 0 {
+                    tail.0 += if dx > 0 { 1 } else { -1 };
+                }
 
-            self.tail_positions.insert(self.knots[self.knots.len() - 1]);
+                tail.1 += if dy > 0 { 1 } else { -1 };
+            }
+        }
+
+        self.tail_positions.insert(self.knots[self.knots.len() - 1]);
+    }
+}
+
+impl Simulation for Rope {
+    /** Position of every knot, head first */
+    type Snapshot = Vec<Position>;
+
+    fn step(&mut self) {
+        if let Some(direction) = self.moves.pop_front() {
+            self.move_head(direction);
         }
     }
+
+    fn is_done(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.knots.clone()
+    }
+}
+
+impl Scene for Rope {
+    /** The head, flipped so up is up on screen */
+    fn focus(&self) -> (i32, i32) {
+        let (x, y) = self.knots[0];
+
+        (x, -y)
+    }
+
+    fn render(&self, viewport: &Viewport) -> String {
+        let mut result = String::new();
+        let last = self.knots.len() - 1;
+
+        for screen_y in viewport.top..=viewport.bottom() {
+            for x in viewport.left..=viewport.right() {
+                let position = (x, -screen_y);
+                // Knots nearer the head are drawn on top
+                let knot = self.knots.iter().position(|knot| *knot == position);
+
+                result.push(match knot {
+                    Some(0) => 'H',
+                    Some(i) if i == last => 'T',
+                    Some(i) => char::from_digit(i as u32 % 10, 10).unwrap(),
+                    None if position == (0, 0) => 's',
+                    None if self.tail_positions.contains(&position) => '#',
+                    None => '.',
+                });
+            }
+
+            result.push('\n');
+        }
+
+        result
+    }
 }
 
 fn parse_input(input: &[String]) -> Vec<(Direction, usize)> {
@@ -97,24 +190,20 @@ fn parse_input(input: &[String]) -> Vec<(Direction, usize)> {
         .collect()
 }
 
-fn part1(input: &[String]) -> usize {
-    let steps = parse_input(input);
-    let mut rope = Rope::new(2);
+pub fn part1(input: &[String]) -> usize {
+    let mut rope = Rope::from_input(input, 2);
 
-    for (direction, distance) in steps {
-        rope.move_head(direction, distance);
-    }
+    let moves = simulation::run(&mut rope, &mut []);
+    event!(Level::Debug, "moved", knots = 2, moves = moves);
 
-    rope.tail_positions.len()
+    rope.tail_visits()
 }
 
-fn part2(input: &[String]) -> usize {
-    let steps = parse_input(input);
-    let mut rope = Rope::new(10);
+pub fn part2(input: &[String]) -> usize {
+    let mut rope = Rope::from_input(input, 10);
 
-    for (direction, distance) in steps {
-        rope.move_head(direction, distance);
-    }
+    let moves = simulation::run(&mut rope, &mut []);
+    event!(Level::Debug, "moved", knots = 10, moves = moves);
 
-    rope.tail_positions.len()
+    rope.tail_visits()
 }
@@ -1,10 +1,23 @@
 use core::panic;
 use std::collections::VecDeque;
 
+use crate::{
+    animation::{Animator, Scene, Viewport},
+    event,
+    simulation::{self, Control, Observer, Simulation},
+    trace::Level,
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
-    println!("Part 2:");
-    part2(&input);
+    print!("Part 2:\n{}", part2(&input));
+}
+
+/** Watch the CRT draw, pixel by pixel */
+pub fn animate(input: Vec<String>, mut animator: Animator) {
+    let mut device = Device::new(&parse_input(&input));
+
+    simulation::run(&mut device, &mut [&mut animator]);
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -13,7 +26,8 @@ enum Instruction {
     AddX(i32),
 }
 
-struct Cpu {
+/** The handheld's CPU, one cycle per step */
+pub struct Cpu {
     cycle: usize,
     register: i32,
     /** The currently executing instruction */
@@ -31,6 +45,14 @@ impl Cpu {
         }
     }
 
+    /** A CPU with the program in `input` loaded */
+    pub fn from_input(input: &[String]) -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.load(&parse_input(input));
+
+        cpu
+    }
+
     fn tick(&mut self) {
         // Start cycle
         match self.instruction {
@@ -62,12 +84,42 @@ impl Cpu {
         self.program = VecDeque::from_iter(program.iter().copied())
     }
 
+    /** Signal strength during the current cycle */
+    pub fn signal(&self) -> i32 {
+        (self.cycle as i32) * self.register
+    }
+}
+
+impl Simulation for Cpu {
+    /** `(cycle, register)` */
+    type Snapshot = (usize, i32);
+
+    fn step(&mut self) {
+        self.tick();
+    }
+
     fn is_done(&self) -> bool {
         self.instruction.is_none() && self.program.is_empty()
     }
 
-    fn signal(&self) -> i32 {
-        (self.cycle as i32) * self.register
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.cycle, self.register)
+    }
+}
+
+/** Sums the signal strength at the end of each of the given cycles */
+struct SignalStrength {
+    cycles: Vec<usize>,
+    total: i32,
+}
+
+impl Observer<Cpu> for SignalStrength {
+    fn after_step(&mut self, cpu: &Cpu) -> Control {
+        if self.cycles.contains(&cpu.cycle) {
+            self.total += cpu.signal();
+        }
+
+        Control::Continue
     }
 }
 
@@ -114,44 +166,108 @@ impl Crt {
         };
     }
 
-    fn display(&self) {
-        for row in self.buffer {
-            for col in row {
-                print!("{}", col);
+    fn render(&self, viewport: &Viewport) -> String {
+        let mut result = String::new();
+
+        for y in viewport.top..=viewport.bottom() {
+            for x in viewport.left..=viewport.right() {
+                let pixel = usize::try_from(y)
+                    .ok()
+                    .zip(usize::try_from(x).ok())
+                    .and_then(|(row, col)| self.buffer.get(row)?.get(col));
+
+                result.push(*pixel.unwrap_or(&' '));
             }
 
-            println!();
+            result.push('\n');
         }
+
+        result
+    }
+
+    /** The whole screen */
+    fn display(&self) -> String {
+        self.render(&Viewport::new(40, 6))
     }
 }
 
-fn part1(input: &[String]) -> i32 {
-    let program = parse_input(input);
-    let mut cpu = Cpu::new();
-    cpu.load(&program);
-    let mut result = 0;
+/** A CPU wired up to the CRT it drives */
+struct Device {
+    cpu: Cpu,
+    crt: Crt,
+}
 
-    while !cpu.is_done() {
-        cpu.tick();
+impl Device {
+    fn new(program: &[Instruction]) -> Device {
+        let mut cpu = Cpu::new();
+        cpu.load(program);
 
-        if [20, 60, 100, 140, 180, 220].contains(&cpu.cycle) {
-            result += cpu.signal()
+        Device {
+            cpu,
+            crt: Crt::new(),
         }
     }
+}
+
+impl Simulation for Device {
+    type Snapshot = <Cpu as Simulation>::Snapshot;
+
+    fn step(&mut self) {
+        // Pixels are drawn during a cycle, so before the CPU ticks
+        self.crt.draw(&self.cpu);
+        self.cpu.tick();
+    }
+
+    fn is_done(&self) -> bool {
+        self.cpu.is_done()
+    }
 
-    result
+    fn snapshot(&self) -> Self::Snapshot {
+        self.cpu.snapshot()
+    }
 }
 
-fn part2(input: &[String]) {
-    let program = parse_input(input);
-    let mut cpu = Cpu::new();
-    let mut crt = Crt::new();
-    cpu.load(&program);
+impl Scene for Device {
+    /** The pixel that was last drawn */
+    fn focus(&self) -> (i32, i32) {
+        let pixel = self.cpu.cycle.saturating_sub(1);
 
-    while !cpu.is_done() {
-        crt.draw(&cpu);
-        cpu.tick();
+        ((pixel % 40) as i32, (pixel / 40) as i32)
     }
 
-    crt.display();
+    fn render(&self, viewport: &Viewport) -> String {
+        self.crt.render(viewport)
+    }
+
+    fn bounds(&self) -> Option<((i32, i32), (i32, i32))> {
+        Some(((0, 39), (0, 5)))
+    }
+}
+
+pub fn part1(input: &[String]) -> i32 {
+    let mut cpu = Cpu::from_input(input);
+    let mut signal_strength = SignalStrength {
+        cycles: vec![20, 60, 100, 140, 180, 220],
+        total: 0,
+    };
+
+    let cycles = simulation::run(&mut cpu, &mut [&mut signal_strength]);
+    event!(
+        Level::Debug,
+        "ran",
+        cycles = cycles,
+        register = cpu.register,
+        signal = signal_strength.total
+    );
+
+    signal_strength.total
+}
+
+pub fn part2(input: &[String]) -> String {
+    let mut device = Device::new(&parse_input(input));
+
+    let cycles = simulation::run(&mut device, &mut []);
+    event!(Level::Debug, "drawn", cycles = cycles);
+
+    device.crt.display()
 }
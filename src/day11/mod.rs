@@ -1,5 +1,11 @@
 use std::{collections::VecDeque, vec};
 
+use crate::{
+    event,
+    simulation::{self, Simulation},
+    trace::Level,
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
@@ -16,7 +22,7 @@ struct Test {
 
 impl Test {
     fn execute(&self, worry_score: u64) -> u64 {
-        if worry_score % self.divisor == 0 {
+        if worry_score.is_multiple_of(self.divisor) {
             self.on_pass
         } else {
             self.on_fail
@@ -71,6 +77,88 @@ impl Monkey {
     }
 }
 
+/** A game of keep away, played one round at a time */
+pub struct KeepAway {
+    monkeys: Vec<Monkey>,
+    round: usize,
+    rounds: usize,
+    /** Worry levels are divided by this after inspection */
+    relief: u64,
+    /** Worry levels are kept modulo this to stop them growing unbounded */
+    mod_val: u64,
+}
+
+impl KeepAway {
+    fn new(monkeys: Vec<Monkey>, rounds: usize, relief: u64, mod_val: u64) -> KeepAway {
+        KeepAway {
+            monkeys,
+            round: 0,
+            rounds,
+            relief,
+            mod_val,
+        }
+    }
+
+    /**
+     * The game described by `input`. Without relief, worry levels are kept modulo the product
+     * of every monkey's divisor, which doesn't change where items are thrown
+     */
+    pub fn from_input(input: &[String], rounds: usize, relief: u64) -> KeepAway {
+        let monkeys = parse_input(input);
+        let mod_val = if relief == 1 {
+            monkeys
+                .iter()
+                .fold(1, |acc, monkey| acc * monkey.test.divisor)
+        } else {
+            u64::MAX
+        };
+
+        KeepAway::new(monkeys, rounds, relief, mod_val)
+    }
+
+    /** Product of the two highest inspection counts */
+    pub fn monkey_business(&self) -> u64 {
+        let mut inspections = self.snapshot();
+        inspections.sort_by_key(|it| std::cmp::Reverse(*it));
+
+        inspections.iter().take(2).product()
+    }
+}
+
+impl Simulation for KeepAway {
+    /** Inspections made by each monkey */
+    type Snapshot = Vec<u64>;
+
+    fn step(&mut self) {
+        for i in 0..self.monkeys.len() {
+            let result = self.monkeys[i].take_turn(self.relief, self.mod_val);
+
+            for (item, next_monkey) in result.iter() {
+                self.monkeys[*next_monkey].items.push_back(*item);
+            }
+        }
+
+        self.round += 1;
+        event!(
+            Level::Trace,
+            "round",
+            round = self.round,
+            inspections = self.snapshot()
+        );
+    }
+
+    fn is_done(&self) -> bool {
+        self.round >= self.rounds
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.monkeys
+            .iter()
+            .map(|monkey| monkey.inspections_made)
+            .collect()
+    }
+}
+
 fn parse_input(input: &[String]) -> Vec<Monkey> {
     let mut monkeys = vec![];
     let mut iter = input.iter();
@@ -133,52 +221,23 @@ fn parse_input(input: &[String]) -> Vec<Monkey> {
         iter.next();
     }
 
+    event!(Level::Debug, "parsed", monkeys = monkeys.len());
+
     monkeys
 }
 
-fn part1(input: &[String]) -> u64 {
-    let mut monkeys = parse_input(input);
+pub fn part1(input: &[String]) -> u64 {
+    let mut game = KeepAway::from_input(input, 20, 3);
 
-    // 20 rounds
-    for _ in 1..=20 {
-        for i in 0..monkeys.len() {
-            let result = monkeys[i].take_turn(3, u64::MAX);
+    simulation::run(&mut game, &mut []);
 
-            for (item, next_monkey) in result.iter() {
-                monkeys[*next_monkey].items.push_back(*item);
-            }
-        }
-    }
-
-    monkeys.sort_by(|a, b| b.inspections_made.cmp(&a.inspections_made));
-
-    monkeys
-        .iter()
-        .take(2)
-        .fold(1, |acc, monkey| acc * monkey.inspections_made)
+    game.monkey_business()
 }
 
-fn part2(input: &[String]) -> u64 {
-    let mut monkeys = parse_input(input);
-    let mod_val = monkeys
-        .iter()
-        .fold(1, |acc, monkey| acc * monkey.test.divisor);
-
-    // 10,000 rounds
-    for _ in 1..=10_000 {
-        for i in 0..monkeys.len() {
-            let result = monkeys[i].take_turn(1, mod_val);
-
-            for (item, next_monkey) in result.iter() {
-                monkeys[*next_monkey].items.push_back(*item);
-            }
-        }
-    }
+pub fn part2(input: &[String]) -> u64 {
+    let mut game = KeepAway::from_input(input, 10_000, 1);
 
-    monkeys.sort_by(|a, b| b.inspections_made.cmp(&a.inspections_made));
+    simulation::run(&mut game, &mut []);
 
-    monkeys
-        .iter()
-        .take(2)
-        .fold(1, |acc, monkey| (acc * monkey.inspections_made))
+    game.monkey_business()
 }
@@ -3,11 +3,42 @@ use std::{
     ops::Add,
 };
 
+use crate::{
+    event,
+    image::{Image, Rgb},
+    trace::Level,
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
+/** The height map, with the shortest path from start to target drawn over it */
+pub fn image(input: &[String]) -> Image {
+    let (map, start, target) = parse_input(input);
+    let mut image = Image::new(map.width, map.height, Rgb::BLACK);
+
+    for y in 0..map.height {
+        for x in 0..map.width {
+            let height = height_difference(map.get_height(Position(x, y)), 'a');
+            let color = Rgb(30, 60, 120).lerp(Rgb(240, 240, 230), height as f64 / 25.0);
+            image.set(x, y, color);
+        }
+    }
+
+    for Position(x, y) in map.find_path(start, target) {
+        image.set(x, y, Rgb(220, 40, 40));
+    }
+
+    let Position(x, y) = start;
+    image.set(x, y, Rgb(40, 200, 40));
+    let Position(x, y) = target;
+    image.set(x, y, Rgb(250, 200, 0));
+
+    image
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
 struct Position(u32, u32);
 
@@ -51,17 +82,42 @@ impl Map {
         self.data[y as usize][x as usize]
     }
 
-    /** Find the shortest path from start->target */
-    fn find_path(&self, start: Position, target: Position) -> u32 {
+    /** Find the shortest path from start->target, returning every position along it */
+    fn find_path(&self, start: Position, target: Position) -> Vec<Position> {
         let mut paths = BinaryHeap::new();
         paths.push(Path::new(start));
         // Records the current known min path to position
         let mut min_paths = HashMap::<Position, u32>::new();
         min_paths.insert(start, 0);
+        // Records where the current known min path to position came from
+        let mut previous = HashMap::<Position, Position>::new();
+        let mut iterations = 0;
 
         while let Some(path) = paths.pop() {
+            iterations += 1;
+            event!(
+                Level::Trace,
+                "visit",
+                position = path.current_position,
+                queue = paths.len()
+            );
+
             if path.current_position == target {
-                return path.length;
+                event!(
+                    Level::Debug,
+                    "found path",
+                    length = path.length,
+                    iterations = iterations
+                );
+
+                let mut route = vec![target];
+
+                while let Some(position) = previous.get(route.last().unwrap()) {
+                    route.push(*position);
+                }
+
+                route.reverse();
+                return route;
             }
 
             let Position(x, y) = path.current_position;
@@ -96,6 +152,7 @@ impl Map {
 
                 if can_reach_position && found_shortest_path {
                     min_paths.insert(position, path.length + 1);
+                    previous.insert(position, path.current_position);
                     paths.push(Path {
                         current_position: position,
                         length: path.length + 1,
@@ -114,8 +171,17 @@ impl Map {
         // Records the current known min path to position
         let mut min_paths = HashMap::<Position, u32>::new();
         min_paths.insert(start, 0);
+        let mut iterations = 0;
 
         while let Some(path) = paths.pop() {
+            iterations += 1;
+            event!(
+                Level::Trace,
+                "visit",
+                position = path.current_position,
+                queue = paths.len()
+            );
+
             let Position(x, y) = path.current_position;
             let next_positions = vec![
                 Position(x, y.saturating_sub(1)),           // Up
@@ -156,6 +222,13 @@ impl Map {
             }
         }
 
+        event!(
+            Level::Debug,
+            "explored",
+            reachable = min_paths.len(),
+            iterations = iterations
+        );
+
         min_paths
     }
 }
@@ -200,13 +273,13 @@ fn parse_input(input: &[String]) -> (Map, Position, Position) {
     )
 }
 
-fn part1(input: &[String]) -> u32 {
+pub fn part1(input: &[String]) -> u32 {
     let (map, start, target) = parse_input(input);
 
-    map.find_path(start, target)
+    map.find_path(start, target).len() as u32 - 1
 }
 
-fn part2(input: &[String]) -> u32 {
+pub fn part2(input: &[String]) -> u32 {
     let (map, _, target) = parse_input(input);
     let mut min_distance = None;
 
@@ -1,5 +1,7 @@
 use std::{cmp::Ordering, fmt::Debug, str::Chars, vec};
 
+use crate::{event, trace::Level};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
@@ -106,10 +108,12 @@ fn parse_input(input: &[String]) -> Vec<(ListItem, ListItem)> {
         result.push((parse_line(left), parse_line(right)));
     }
 
+    event!(Level::Debug, "parsed", pairs = result.len());
+
     result
 }
 
-fn part1(input: &[String]) -> u32 {
+pub fn part1(input: &[String]) -> u32 {
     let pairs = parse_input(input);
     let mut res = 0;
 
@@ -122,7 +126,7 @@ fn part1(input: &[String]) -> u32 {
     res
 }
 
-fn part2(input: &[String]) -> u32 {
+pub fn part2(input: &[String]) -> u32 {
     let pairs = parse_input(input);
     let divider_1 = ListItem::List(vec![ListItem::List(vec![ListItem::Integer(2)])]);
     let divider_2 = ListItem::List(vec![ListItem::List(vec![ListItem::Integer(6)])]);
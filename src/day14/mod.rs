@@ -1,10 +1,25 @@
 use std::collections::HashMap;
 
+use crate::{
+    animation::{Animator, Scene, Viewport},
+    event,
+    image::{Image, Rgb},
+    simulation::{self, Render, Simulation},
+    trace::{self, Level},
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
+/** Watch the sand pile up */
+pub fn animate(input: Vec<String>, include_floor: bool, mut animator: Animator) {
+    let mut slice = parse_input(&input, include_floor);
+
+    simulation::run(&mut slice, &mut [&mut animator]);
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Material {
     Air,
@@ -12,14 +27,21 @@ enum Material {
     Sand,
 }
 
-#[derive(Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 struct Position(u32, u32);
 
-struct Slice {
+/** A slice of the cave, one unit of sand dropped per step */
+pub struct Slice {
     data: HashMap<Position, Material>,
     height: u32,
     sand_count: u32,
     include_floor: bool,
+    /** Where sand pours in from */
+    source: Position,
+    /** Set once sand stops coming to rest */
+    is_settled: bool,
+    /** Where the most recent unit of sand came to rest */
+    last_sand: Option<Position>,
 }
 
 impl Slice {
@@ -29,32 +51,20 @@ impl Slice {
             sand_count: 0,
             data: HashMap::new(),
             include_floor: false,
+            source: Position(500, 0),
+            is_settled: false,
+            last_sand: None,
         }
     }
 
-    fn print(&self) {
-        let mut x_min = u32::MAX;
-        let mut x_max = u32::MIN;
-
-        for (Position(x, _), _) in self.data.iter() {
-            x_min = x_min.min(*x);
-            x_max = x_max.max(*x);
-        }
-
-        for y in 0..self.height {
-            for x in x_min..=x_max {
-                print!(
-                    "{} ",
-                    match self.get(Position(x, y)) {
-                        Material::Rock => "#",
-                        Material::Sand => "o",
-                        Material::Air => ".",
-                    }
-                );
-            }
+    /** The cave in `input`, with or without the floor */
+    pub fn from_input(input: &[String], include_floor: bool) -> Slice {
+        parse_input(input, include_floor)
+    }
 
-            println!();
-        }
+    /** Units of sand at rest so far */
+    pub fn sand_count(&self) -> u32 {
+        self.sand_count
     }
 
     fn get(&self, Position(x_pos, y_pos): Position) -> Material {
@@ -87,6 +97,7 @@ impl Slice {
             } else if self.get(Position(x_pos, y)) == Material::Air {
                 self.set(Position(x_pos, y), Material::Sand);
                 self.sand_count += 1;
+                self.last_sand = Some(Position(x_pos, y));
                 return true;
             } else {
                 break;
@@ -97,6 +108,139 @@ impl Slice {
     }
 }
 
+impl Simulation for Slice {
+    /** Units of sand at rest */
+    type Snapshot = u32;
+
+    fn step(&mut self) {
+        if !self.drop_sand(self.source) {
+            self.is_settled = true;
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.is_settled
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.sand_count
+    }
+}
+
+impl Scene for Slice {
+    fn focus(&self) -> (i32, i32) {
+        let Position(x, y) = self.last_sand.unwrap_or(self.source);
+
+        (x as i32, y as i32)
+    }
+
+    fn render(&self, viewport: &Viewport) -> String {
+        let mut result = String::new();
+
+        for y in viewport.top..=viewport.bottom() {
+            for x in viewport.left..=viewport.right() {
+                if x < 0 || y < 0 || y as u32 >= self.height {
+                    result.push(' ');
+                    continue;
+                }
+
+                let position = Position(x as u32, y as u32);
+
+                result.push(match self.get(position) {
+                    Material::Rock => '#',
+                    Material::Sand => 'o',
+                    Material::Air if position == self.source => '+',
+                    Material::Air => '.',
+                });
+            }
+
+            result.push('\n');
+        }
+
+        result
+    }
+}
+
+/** The cave once the sand has settled */
+pub fn image(input: &[String], include_floor: bool) -> Image {
+    let mut slice = parse_input(input, include_floor);
+    let bounds = slice.image_bounds();
+
+    simulation::run(&mut slice, &mut []);
+
+    slice.image(bounds)
+}
+
+/** The cave after every `every` units of sand, plus the final state */
+pub fn frames(input: &[String], include_floor: bool, every: usize) -> Vec<Image> {
+    let mut slice = parse_input(input, include_floor);
+    let bounds = slice.image_bounds();
+    let mut frames = vec![slice.image(bounds)];
+    let mut render = Render::new(every, |slice: &Slice| frames.push(slice.image(bounds)));
+
+    simulation::run(&mut slice, &mut [&mut render]);
+    frames.push(slice.image(bounds));
+
+    frames
+}
+
+impl Slice {
+    /** Emit diagnostics for the settled slice, drawing it to stderr at the most verbose level */
+    fn report(&self) {
+        event!(
+            Level::Debug,
+            "settled",
+            sand = self.sand_count,
+            height = self.height,
+            floor = self.include_floor
+        );
+
+        if trace::enabled(Level::Trace) {
+            let (min_x, max_x) = self.image_bounds();
+            let mut viewport = Viewport::new(max_x - min_x + 1, self.height);
+            viewport.left = min_x as i32;
+
+            eprint!("{}", self.render(&viewport));
+        }
+    }
+
+    /**
+     * Inclusive `(min_x, max_x)` that fits both the rock and any sand that comes to rest.
+     * Sand can't settle further sideways from the source than it falls
+     */
+    fn image_bounds(&self) -> (u32, u32) {
+        let Position(source_x, _) = self.source;
+        let mut min_x = source_x.saturating_sub(self.height);
+        let mut max_x = source_x + self.height;
+
+        for Position(x, _) in self.data.keys() {
+            min_x = min_x.min(*x);
+            max_x = max_x.max(*x);
+        }
+
+        (min_x, max_x)
+    }
+
+    fn image(&self, (min_x, max_x): (u32, u32)) -> Image {
+        let mut image = Image::new(max_x - min_x + 1, self.height, Rgb(20, 20, 30));
+
+        for y in 0..self.height {
+            for x in min_x..=max_x {
+                let color = match self.get(Position(x, y)) {
+                    Material::Rock => Rgb(110, 110, 110),
+                    Material::Sand => Rgb(230, 190, 90),
+                    Material::Air if Position(x, y) == self.source => Rgb(220, 60, 60),
+                    Material::Air => continue,
+                };
+
+                image.set(x - min_x, y, color);
+            }
+        }
+
+        image
+    }
+}
+
 fn parse_input(input: &[String], include_floor: bool) -> Slice {
     let line_segments: Vec<Vec<(u32, u32)>> = input
         .iter()
@@ -141,18 +285,20 @@ fn parse_input(input: &[String], include_floor: bool) -> Slice {
     slice
 }
 
-fn part1(input: &[String]) -> u32 {
+pub fn part1(input: &[String]) -> u32 {
     let mut slice = parse_input(input, false);
 
-    while slice.drop_sand(Position(500, 0)) {}
+    simulation::run(&mut slice, &mut []);
+    slice.report();
 
     slice.sand_count
 }
 
-fn part2(input: &[String]) -> u32 {
+pub fn part2(input: &[String]) -> u32 {
     let mut slice = parse_input(input, true);
 
-    while slice.drop_sand(Position(500, 0)) {}
+    simulation::run(&mut slice, &mut []);
+    slice.report();
 
     slice.sand_count
 }
@@ -3,11 +3,72 @@ use std::vec;
 use lazy_static::lazy_static;
 use regex::Regex;
 
+use crate::{
+    event,
+    image::{Image, Rgb},
+    trace::{self, Level},
+};
+
 pub fn run(input: Vec<String>) {
     println!("Part 1: {}", part1(&input));
     println!("Part 2: {}", part2(&input));
 }
 
+/** Sensor coverage scaled down to `width` pixels across, brighter where diamonds overlap */
+pub fn image(input: &[String], width: u32) -> Image {
+    let map = Map::parse(input);
+    let ((min_x, max_x), (min_y, max_y)) = map.get_bounds();
+    let scale = ((max_x - min_x) as f64 / width.max(1) as f64).max(1.0);
+    let height = ((max_y - min_y) as f64 / scale).ceil() as u32 + 1;
+    let width = ((max_x - min_x) as f64 / scale).ceil() as u32 + 1;
+    let to_pixel = |Position(x, y): Position| {
+        (
+            ((x - min_x) as f64 / scale) as u32,
+            ((y - min_y) as f64 / scale) as u32,
+        )
+    };
+    let mut image = Image::new(width, height, Rgb(10, 10, 25));
+
+    for py in 0..height {
+        for px in 0..width {
+            let position = Position(
+                min_x + (px as f64 * scale) as i32,
+                min_y + (py as f64 * scale) as i32,
+            );
+            let coverage = map
+                .sensors
+                .iter()
+                .filter(|sensor| sensor.position.abs_distance(position) <= sensor.radius)
+                .count();
+
+            if coverage > 0 {
+                let color = Rgb(40, 60, 110).lerp(Rgb(150, 200, 255), (coverage - 1) as f64 / 4.0);
+                image.set(px, py, color);
+            }
+        }
+    }
+
+    let mut mark = |position: Position, color: Rgb| {
+        let (px, py) = to_pixel(position);
+
+        for y in py.saturating_sub(1)..=py + 1 {
+            for x in px.saturating_sub(1)..=px + 1 {
+                image.set(x, y, color);
+            }
+        }
+    };
+
+    for sensor in map.sensors.iter() {
+        mark(sensor.position, Rgb(230, 60, 60));
+    }
+
+    for beacon in map.beacons.iter() {
+        mark(*beacon, Rgb(250, 210, 40));
+    }
+
+    image
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum MapCell {
     Unknown,
@@ -171,15 +232,26 @@ impl Map {
             map.add_sensor(sensor, beacon);
         }
 
+        // Bounds mean a pass over every sensor, so only when they'll be shown
+        event!(
+            Level::Debug,
+            "parsed",
+            sensors = map.sensors.len(),
+            bounds = map.get_bounds()
+        );
+
+        if trace::enabled(Level::Trace) {
+            let ((min_x, max_x), (min_y, max_y)) = map.get_bounds();
+            if (max_x - min_x) <= 200 && (max_y - min_y) <= 200 {
+                map.display();
+            }
+        }
+
         map
     }
 
     fn get(&self, position: Position) -> MapCell {
-        if self
-            .beacons
-            .iter()
-            .any(|beacon_position| *beacon_position == position)
-        {
+        if self.beacons.contains(&position) {
             return MapCell::Beacon;
         }
 
@@ -216,14 +288,15 @@ impl Map {
         result
     }
 
+    /** Draw the map to stderr, which is only sensible for small (example sized) inputs */
     fn display(&self) {
         let ((min_x, max_x), (min_y, max_y)) = self.get_bounds();
 
         for y in min_y..=max_y {
-            print!("{:>3} ", y);
+            eprint!("{:>3} ", y);
 
             for x in min_x..=max_x {
-                print!(
+                eprint!(
                     "{}",
                     match self.get(Position(x, y)) {
                         MapCell::Beacon => 'B',
@@ -234,7 +307,7 @@ impl Map {
                 )
             }
 
-            println!();
+            eprintln!();
         }
     }
 
@@ -267,7 +340,7 @@ impl Map {
     }
 }
 
-fn part1(input: &[String]) -> u32 {
+pub fn part1(input: &[String]) -> u32 {
     let map = Map::parse(input);
 
     map.get_row(2_000_000).iter().fold(0, |acc, it| {
@@ -279,7 +352,7 @@ fn part1(input: &[String]) -> u32 {
     })
 }
 
-fn part2(input: &[String]) -> u64 {
+pub fn part2(input: &[String]) -> u64 {
     const LIMIT: u32 = 4_000_000;
     let map = Map::parse(input);
     let mut areas_to_check = vec![];
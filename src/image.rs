@@ -0,0 +1,425 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    /** Blend from `self` (t = 0) to `other` (t = 1) */
+    pub fn lerp(self, other: Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+/** Anything bigger than this is surely a mistake, 768MB of pixels */
+const MAX_PIXELS: usize = 1 << 28;
+
+/** An RGB raster, stored row by row */
+#[derive(Clone)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width as usize * height as usize],
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Rgb {
+        self.pixels[self.index(x, y)]
+    }
+
+    /** Out of bounds writes are ignored, so callers can draw without clipping */
+    pub fn set(&mut self, x: u32, y: u32, color: Rgb) {
+        if x < self.width && y < self.height {
+            let index = self.index(x, y);
+            self.pixels[index] = color;
+        }
+    }
+
+    /** Blow every pixel up into a `factor` x `factor` block. An error if that's too big to hold */
+    pub fn scaled(&self, factor: u32) -> io::Result<Image> {
+        let factor = factor.max(1);
+        let size = self
+            .width
+            .checked_mul(factor)
+            .zip(self.height.checked_mul(factor))
+            .filter(|(width, height)| *width as usize * *height as usize <= MAX_PIXELS);
+        let Some((width, height)) = size else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Scaling by {} makes the image too large", factor),
+            ));
+        };
+        let mut result = Image::new(width, height, Rgb::BLACK);
+
+        for y in 0..result.height {
+            for x in 0..result.width {
+                result.set(x, y, self.get(x / factor, y / factor));
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn write_ppm(&self, out: &mut impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+
+        for Rgb(r, g, b) in self.pixels.iter() {
+            out.write_all(&[*r, *g, *b])?;
+        }
+
+        Ok(())
+    }
+
+    /** Uncompressed (stored deflate blocks) PNG, which every viewer can read */
+    pub fn write_png(&self, out: &mut impl Write) -> io::Result<()> {
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = vec![];
+        header.extend(self.width.to_be_bytes());
+        header.extend(self.height.to_be_bytes());
+        // 8 bit depth, truecolor, deflate, adaptive filtering, no interlace
+        header.extend([8, 2, 0, 0, 0]);
+        write_png_chunk(out, b"IHDR", &header)?;
+
+        let mut scanlines =
+            Vec::with_capacity(self.height as usize * (self.width as usize * 3 + 1));
+        for row in self.pixels.chunks(self.width as usize) {
+            scanlines.push(0); // Filter type: none
+            for Rgb(r, g, b) in row {
+                scanlines.extend([*r, *g, *b]);
+            }
+        }
+        write_png_chunk(out, b"IDAT", &zlib_stored(&scanlines))?;
+
+        write_png_chunk(out, b"IEND", &[])
+    }
+
+    /** Write as PPM or PNG, depending on the file extension */
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+
+        match extension(path).as_str() {
+            "ppm" => self.write_ppm(&mut out)?,
+            "png" => self.write_png(&mut out)?,
+            other => return Err(unsupported(other)),
+        }
+
+        out.flush()
+    }
+}
+
+/**
+ * Write `frames` as a looping animated GIF, showing each for `delay` hundredths of a second.
+ * GIF sizes are 16 bit, so neither side can be over 65535
+ */
+pub fn write_gif(frames: &[Image], delay: u16, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = frames
+        .first()
+        .map(|frame| (frame.width, frame.height))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "No frames to write"))?;
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}x{} is too large for a GIF", width, height),
+        ));
+    };
+
+    // A single global palette, shared by every frame
+    let mut palette: Vec<Rgb> = vec![];
+    let mut palette_index = HashMap::new();
+    let mut indexed_frames = vec![];
+
+    for frame in frames {
+        if (frame.width, frame.height) != (width as u32, height as u32) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Frames must all be the same size",
+            ));
+        }
+
+        let mut indices = Vec::with_capacity(frame.pixels.len());
+        for color in frame.pixels.iter() {
+            let index = *palette_index.entry(*color).or_insert_with(|| {
+                palette.push(*color);
+                palette.len() - 1
+            });
+
+            if index > 255 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "GIFs are limited to 256 colours",
+                ));
+            }
+
+            indices.push(index as u8);
+        }
+
+        indexed_frames.push(indices);
+    }
+
+    // Palette size must be a power of two, at least 4 for LZW's minimum code size of 2
+    let color_bits = (palette.len().max(4) as u32)
+        .next_power_of_two()
+        .trailing_zeros();
+    palette.resize(1 << color_bits, Rgb::BLACK);
+
+    out.write_all(b"GIF89a")?;
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    // Global colour table present, 8 bit colour resolution, table size
+    out.write_all(&[0xF0 | (color_bits as u8 - 1), 0, 0])?;
+    for Rgb(r, g, b) in palette.iter() {
+        out.write_all(&[*r, *g, *b])?;
+    }
+
+    // Netscape extension: loop forever
+    out.write_all(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+    for indices in indexed_frames {
+        // Graphic control extension: frame delay
+        out.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        out.write_all(&delay.to_le_bytes())?;
+        out.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor covering the whole canvas, no local colour table
+        out.write_all(&[0x2C, 0, 0, 0, 0])?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        out.write_all(&[0x00])?;
+
+        out.write_all(&[color_bits as u8])?;
+        for block in lzw_encode(&indices, color_bits).chunks(255) {
+            out.write_all(&[block.len() as u8])?;
+            out.write_all(block)?;
+        }
+        out.write_all(&[0x00])?;
+    }
+
+    out.write_all(&[0x3B])
+}
+
+pub fn save_gif(frames: &[Image], delay: u16, path: &Path) -> io::Result<()> {
+    match extension(path).as_str() {
+        "gif" => {
+            let mut out = BufWriter::new(File::create(path)?);
+            write_gif(frames, delay, &mut out)?;
+
+            out.flush()
+        }
+        other => Err(unsupported(other)),
+    }
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn unsupported(extension: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("Unsupported image format: '{}'", extension),
+    )
+}
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let mut crc_data = kind.to_vec();
+    crc_data.extend_from_slice(data);
+    out.write_all(&crc32(&crc_data).to_be_bytes())
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in data {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/** Wrap `data` in a zlib stream without compressing it */
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut result = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(u16::MAX as usize).collect();
+
+    if blocks.is_empty() {
+        // Final, empty stored block
+        result.extend([0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+
+    for (i, block) in blocks.iter().enumerate() {
+        let is_final = i == blocks.len() - 1;
+        let length = block.len() as u16;
+
+        result.push(is_final as u8);
+        result.extend(length.to_le_bytes());
+        result.extend((!length).to_le_bytes());
+        result.extend_from_slice(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    result.extend(((b << 16) | a).to_be_bytes());
+
+    result
+}
+
+/** Variable width LZW, as GIF uses it, with codes packed least significant bit first */
+fn lzw_encode(indices: &[u8], min_code_size: u32) -> Vec<u8> {
+    const MAX_CODE: u32 = 4095;
+
+    let clear_code = 1u32 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut result = vec![];
+    let mut bit_buffer = 0u32;
+    let mut bit_count = 0;
+    let mut emit = |code: u32, code_size: u32, result: &mut Vec<u8>| {
+        bit_buffer |= code << bit_count;
+        bit_count += code_size;
+
+        while bit_count >= 8 {
+            result.push(bit_buffer as u8);
+            bit_buffer >>= 8;
+            bit_count -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end_code + 1;
+
+    emit(clear_code, code_size, &mut result);
+
+    let mut iter = indices.iter();
+    if let Some(first) = iter.next() {
+        let mut prefix = *first as u32;
+
+        for index in iter {
+            if let Some(code) = table.get(&(prefix, *index)) {
+                prefix = *code;
+                continue;
+            }
+
+            emit(prefix, code_size, &mut result);
+
+            if next_code > MAX_CODE {
+                // Table is full, start again
+                emit(clear_code, code_size, &mut result);
+                table.clear();
+                code_size = min_code_size + 1;
+                next_code = end_code + 1;
+            } else {
+                table.insert((prefix, *index), next_code);
+
+                if next_code == 1 << code_size {
+                    code_size += 1;
+                }
+                next_code += 1;
+            }
+
+            prefix = *index as u32;
+        }
+
+        emit(prefix, code_size, &mut result);
+    }
+
+    emit(end_code, code_size, &mut result);
+    if bit_count > 0 {
+        result.push(bit_buffer as u8);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, write_gif, Image, Rgb};
+
+    #[test]
+    fn crc32_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn png_has_the_signature_and_size() {
+        let mut out = vec![];
+        Image::new(3, 2, Rgb::WHITE).write_png(&mut out).unwrap();
+
+        assert_eq!(&out[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&out[12..16], b"IHDR");
+        assert_eq!(&out[16..24], [0, 0, 0, 3, 0, 0, 0, 2]);
+        assert_eq!(&out[out.len() - 8..out.len() - 4], b"IEND");
+    }
+
+    #[test]
+    fn gif_has_the_header_size_and_trailer() {
+        let mut image = Image::new(5, 4, Rgb::BLACK);
+        image.set(1, 1, Rgb::WHITE);
+        let mut out = vec![];
+        write_gif(&[image.clone(), image], 10, &mut out).unwrap();
+
+        assert_eq!(&out[..6], b"GIF89a");
+        assert_eq!(&out[6..10], [5, 0, 4, 0]);
+        assert_eq!(out.last(), Some(&0x3B));
+    }
+
+    #[test]
+    fn gif_rejects_sizes_over_16_bits() {
+        let mut out = vec![];
+
+        assert!(write_gif(&[Image::new(70_000, 1, Rgb::BLACK)], 10, &mut out).is_err());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn scaling_rejects_overflowing_sizes() {
+        let image = Image::new(2, 2, Rgb::BLACK);
+
+        assert!(image.scaled(u32::MAX).is_err());
+        assert_eq!(image.scaled(3).unwrap().width, 6);
+    }
+}
@@ -1,54 +1,73 @@
 use std::{
+    env,
     fs::File,
     io::{self, stdin, stdout, BufRead, BufReader, Result, Write},
+    num::NonZeroUsize,
     path::Path,
+    time::Instant,
 };
 
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
+use advent_of_code_2022::{
+    animation::{Animator, Viewport},
+    cli::Args,
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, event,
+    image::{self, Image},
+    server,
+    trace::{self, Level},
+};
 
 fn main() -> Result<()> {
-    let stdin = stdin();
-    let mut input: String;
-    let day: u32;
+    let args = Args::parse(env::args().skip(1));
+    let verbosity = args
+        .count('v')
+        .max(args.parse_value("verbose", 0).map_err(invalid_input)?);
+    trace::set_verbosity(verbosity.min(u8::MAX as usize) as u8);
 
-    loop {
-        input = String::new();
-        print!("Which day [1-25]: ");
-        stdout().flush()?;
-        stdin.read_line(&mut input)?;
+    if args
+        .positional()
+        .first()
+        .is_some_and(|command| command == "serve")
+    {
+        let address = format!(
+            "127.0.0.1:{}",
+            args.parse_value("port", 8080).map_err(invalid_input)?
+        );
 
-        match input.trim().parse::<u32>() {
-            Ok(value) => {
-                day = value;
-                break;
-            }
-            Err(_) => println!("Invalid input: {}", input),
-        }
+        return server::serve(&address);
     }
 
+    let day = match args.positional().first() {
+        Some(day) => day
+            .parse::<u32>()
+            .map_err(|_| invalid_input(format!("Invalid day: {}", day)))?,
+        None => prompt_day()?,
+    };
+
     let path_string = format!("src/day{:0>2}/input.txt", day);
     let path = Path::new(&path_string);
     let file = File::open(path).unwrap();
     let reader = BufReader::new(file);
-    let lines: Vec<String> = reader.lines().filter_map(io::Result::ok).collect();
+    let lines: Vec<String> = reader.lines().map_while(io::Result::ok).collect();
+
+    if args.has("animate") {
+        return animate(day, lines, &args).map_err(invalid_input);
+    }
+
+    if let Some(file) = args.value("export") {
+        return export(day, &lines, Path::new(file), &args).map_err(invalid_input);
+    }
+
+    if let Some(mode) = args.positional().get(1) {
+        return run_mode(day, mode, lines, &args).map_err(invalid_input);
+    }
+
+    let start = Instant::now();
 
     match day {
         1 => day01::run(lines),
         2 => day02::run(lines),
+        3 if args.has("strict") => day03::run_strict(lines).map_err(invalid_input)?,
         3 => day03::run(lines),
         4 => day04::run(lines),
         5 => day05::run(lines),
@@ -65,5 +84,186 @@ fn main() -> Result<()> {
         _ => {}
     }
 
+    event!(Level::Info, "solved", day = day, elapsed = start.elapsed());
+
+    Ok(())
+}
+
+fn prompt_day() -> Result<u32> {
+    let stdin = stdin();
+    let mut input: String;
+
+    loop {
+        input = String::new();
+        print!("Which day [1-25]: ");
+        stdout().flush()?;
+        stdin.read_line(&mut input)?;
+
+        match input.trim().parse::<u32>() {
+            Ok(value) => return Ok(value),
+            Err(_) => println!("Invalid input: {}", input),
+        }
+    }
+}
+
+/** `<day> <mode> [options]`, for the extra tools some days have */
+fn run_mode(
+    day: u32,
+    mode: &str,
+    lines: Vec<String>,
+    args: &Args,
+) -> std::result::Result<(), String> {
+    match (day, mode) {
+        (1, "summary") => day01::summary(lines, args.parse_value("top", 3)?),
+        (1, "stream") => {
+            let k = args.parse_value("top", 3)?;
+
+            match args.value("file") {
+                Some(file) => {
+                    let file = File::open(file).map_err(|err| err.to_string())?;
+                    day01::stream(BufReader::new(file), k)
+                }
+                None => day01::stream(stdin().lock(), k),
+            }
+        }
+        (2, "rules") => day02::run_with_rules(lines, args.value("rules").unwrap_or("standard"))?,
+        (2, "decode") => day02::decode(lines, args.value("rules").unwrap_or("standard"))?,
+        (2, "tournament") => day02::tournament(
+            lines,
+            args.value("rules").unwrap_or("standard"),
+            args.parse_value("rounds", 1000)?,
+            args.parse_value("seed", 1)?,
+        )?,
+        (3, "shared") => {
+            // Groups need at least one member, so zero doesn't parse
+            let grouping = match args.value("group") {
+                Some(_) => day03::Grouping::Lines(
+                    args.parse_value("group", NonZeroUsize::new(3).unwrap())?,
+                ),
+                None => day03::Grouping::Compartments(
+                    args.parse_value("compartments", NonZeroUsize::new(2).unwrap())?,
+                ),
+            };
+
+            day03::shared_report(lines, grouping)
+        }
+        (3, "validate") => day03::validation_report(lines),
+        (4, "sweep") => day04::sweep_report(lines, args.parse_value("limit", 10)?),
+        (4, "relations") => day04::relation_report(lines),
+        (5, "crane") => day05::run_with_crane(
+            lines,
+            args.value("crane").unwrap_or("9001"),
+            args.value("policy").unwrap_or("clamp"),
+        )?,
+        (5, "diagram") => day05::diagram(
+            lines,
+            args.value("crane").unwrap_or("9001"),
+            args.value("at")
+                .map(|_| args.parse_value("at", 0))
+                .transpose()?,
+            args.has("every"),
+        )?,
+        (5, "step") => {
+            day05::step_mode(lines, args.value("crane").unwrap_or("9001"), stdin().lock())?
+        }
+        (6, "decode") => {
+            let packet = args.parse_value("packet", 4)?;
+            let message = args.parse_value("message", 14)?;
+
+            match args.value("file") {
+                Some(file) => {
+                    let file = File::open(file).map_err(|err| err.to_string())?;
+                    day06::decode(file, packet, message)
+                }
+                None => day06::decode(stdin().lock(), packet, message),
+            }
+            .map_err(|err| err.to_string())?
+        }
+        (7, "tree" | "du") => {
+            let options = day07::report::Options {
+                depth: args
+                    .value("depth")
+                    .map(|_| args.parse_value("depth", 0))
+                    .transpose()?,
+                sort: day07::report::Sort::parse(args.value("sort").unwrap_or("listed"))?,
+                min_size: args.parse_value("min", 0)?,
+                directories_only: args.has("dirs"),
+                human: args.has("human"),
+            };
+
+            day07::print_report(lines, mode, &options)?
+        }
+        (7, "check") => day07::check(lines),
+        (7, "import") => day07::import(Path::new(
+            args.value("file").ok_or("import needs --file=PATH")?,
+        ))?,
+        (7, "roundtrip") => day07::round_trip(lines)?,
+        (7, "plan") => day07::print_plans(
+            lines,
+            args.parse_value("capacity", 70000000)?,
+            args.parse_value("required", 30000000)?,
+            args.value("strategy")
+                .map(day07::planner::Strategy::parse)
+                .transpose()?,
+        ),
+        _ => return Err(format!("Day {} has no '{}' mode", day, mode)),
+    }
+
+    Ok(())
+}
+
+/** `--animate [--part=N] [--fps=N] [--width=N] [--height=N]` */
+fn animate(day: u32, lines: Vec<String>, args: &Args) -> std::result::Result<(), String> {
+    let part: u32 = args.parse_value("part", 2)?;
+    let fps = args.parse_value("fps", 30)?;
+    let viewport = Viewport::new(
+        args.parse_value("width", 80)?,
+        args.parse_value("height", 24)?,
+    );
+
+    match day {
+        9 => day09::animate(
+            lines,
+            if part == 1 { 2 } else { 10 },
+            Animator::new(fps, viewport),
+        ),
+        10 => day10::animate(lines, Animator::new(fps, viewport)),
+        14 => day14::animate(lines, part == 2, Animator::new(fps, viewport)),
+        _ => return Err(format!("Day {} can't be animated", day)),
+    }
+
     Ok(())
 }
+
+/** `--export=FILE [--part=N] [--scale=N] [--width=N] [--every=N] [--delay=N]` */
+fn export(day: u32, lines: &[String], path: &Path, args: &Args) -> std::result::Result<(), String> {
+    let part: u32 = args.parse_value("part", 2)?;
+    let scale = args.parse_value("scale", 1)?;
+    let is_gif = path.extension().is_some_and(|extension| extension == "gif");
+
+    let frames = match day {
+        7 => return day07::export(lines, path),
+        8 => vec![day08::image(lines)],
+        12 => vec![day12::image(lines)],
+        14 if is_gif => day14::frames(lines, part == 2, args.parse_value("every", 100)?),
+        14 => vec![day14::image(lines, part == 2)],
+        15 => vec![day15::image(lines, args.parse_value("width", 800)?)],
+        _ => return Err(format!("Day {} can't be exported", day)),
+    };
+    let frames: Vec<Image> = frames
+        .iter()
+        .map(|frame| frame.scaled(scale))
+        .collect::<io::Result<_>>()
+        .map_err(|err| err.to_string())?;
+
+    if is_gif {
+        image::save_gif(&frames, args.parse_value("delay", 5)?, path)
+    } else {
+        frames[0].save(path)
+    }
+    .map_err(|err| err.to_string())
+}
+
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
@@ -0,0 +1,346 @@
+use std::{
+    io::{self, BufRead, BufReader, ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    json,
+    solver::{self, SolveError},
+};
+
+/** Puzzle inputs are a few tens of KB, anything much bigger is a mistake */
+const MAX_BODY_SIZE: usize = 16 * 1024 * 1024;
+
+/** Any more than this, and the client isn't sending a real request */
+const MAX_HEADERS: usize = 100;
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+
+/** How long a client has to send its whole request, however slowly it trickles in */
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/** Connections handled at once. Any more wait in the queue, and past that are turned away */
+const WORKERS: usize = 8;
+const QUEUE_SIZE: usize = 64;
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: format!("{{\"error\":{}}}", json::quote(message)),
+        }
+    }
+}
+
+/**
+ * Serve the solvers over HTTP, with a fixed pool of threads handling connections.
+ *
+ * `POST /day/{n}/part/{p}` with the puzzle input as the body responds with
+ * `{"day":n,"part":p,"answer":"...","elapsed_ms":...}`
+ */
+pub fn serve(address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+
+    let (sender, receiver) = mpsc::sync_channel::<TcpStream>(QUEUE_SIZE);
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || work(&receiver));
+    }
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => match sender.try_send(stream) {
+                Ok(()) => {}
+                Err(TrySendError::Full(mut stream)) => {
+                    let response = Response::error(503, "Too busy, try again later");
+                    if let Err(err) = write_response(&mut stream, &response) {
+                        eprintln!("Connection error: {}", err);
+                    }
+                }
+                Err(TrySendError::Disconnected(_)) => break,
+            },
+            Err(err) => eprintln!("Failed to accept connection: {}", err),
+        }
+    }
+
+    Ok(())
+}
+
+/** Handle queued connections one after another, until the queue is gone */
+fn work(receiver: &Mutex<Receiver<TcpStream>>) {
+    loop {
+        // The lock is only held while waiting, not while handling
+        let Ok(stream) = receiver.lock().unwrap().recv() else {
+            return;
+        };
+
+        if let Err(err) = handle_connection(stream) {
+            eprintln!("Connection error: {}", err);
+        }
+    }
+}
+
+fn handle_connection(mut stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(Deadline {
+        stream: stream.try_clone()?,
+        at: Instant::now() + REQUEST_TIMEOUT,
+    });
+    let response = read_request(&mut reader)?;
+
+    write_response(&mut stream, &response)
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason_phrase(response.status),
+        response.body.len(),
+        response.body
+    )?;
+
+    stream.flush()
+}
+
+/** Reads that fail once `at` has passed, however much is still arriving */
+struct Deadline {
+    stream: TcpStream,
+    at: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.at.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(ErrorKind::TimedOut, "Request took too long"));
+        }
+
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+/** Read a line, giving up after `MAX_LINE_LENGTH` bytes. `None` if it was longer */
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+    reader.take(MAX_LINE_LENGTH).read_line(&mut line)?;
+
+    if line.len() as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Ok(None);
+    }
+
+    Ok(Some(line))
+}
+
+fn read_request(reader: &mut impl BufRead) -> io::Result<Response> {
+    let Some(request_line) = read_line(reader)? else {
+        return Ok(Response::error(414, "Request line too long"));
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Ok(Response::error(400, "Malformed request line"));
+    };
+
+    let mut content_length = 0;
+    for i in 0.. {
+        if i == MAX_HEADERS {
+            return Ok(Response::error(431, "Too many headers"));
+        }
+
+        let Some(header) = read_line(reader)? else {
+            return Ok(Response::error(431, "Header too long"));
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok(Response::error(400, "Invalid Content-Length")),
+                }
+            }
+        }
+    }
+
+    let Some((day, part)) = parse_route(path) else {
+        return Ok(Response::error(404, "Expected /day/{n}/part/{p}"));
+    };
+
+    if method != "POST" {
+        return Ok(Response::error(405, "Only POST is supported"));
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Ok(Response::error(413, "Input too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    let Ok(body) = String::from_utf8(body) else {
+        return Ok(Response::error(400, "Input must be UTF-8"));
+    };
+    let input: Vec<String> = body.lines().map(String::from).collect();
+
+    let start = Instant::now();
+    let result = solver::solve(day, part, &input);
+    let elapsed = start.elapsed();
+
+    Ok(match result {
+        Ok(answer) => Response {
+            status: 200,
+            body: format!(
+                "{{\"day\":{},\"part\":{},\"answer\":{},\"elapsed_ms\":{:.3}}}",
+                day,
+                part,
+                json::quote(&answer),
+                elapsed.as_secs_f64() * 1000.0
+            ),
+        },
+        Err(SolveError::NotImplemented) => {
+            Response::error(404, &format!("Day {} part {} isn't implemented", day, part))
+        }
+        Err(SolveError::InvalidInput(message)) => {
+            Response::error(422, &format!("Couldn't parse input: {}", message))
+        }
+        Err(SolveError::Panicked(message)) => {
+            Response::error(500, &format!("Solver failed: {}", message))
+        }
+    })
+}
+
+/** `/day/{n}/part/{p}` -> `(n, p)` */
+fn parse_route(path: &str) -> Option<(u32, u32)> {
+    let mut segments = path.trim_matches('/').split('/');
+
+    match (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) {
+        (Some("day"), Some(day), Some("part"), Some(part), None) => {
+            Some((day.parse().ok()?, part.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        414 => "URI Too Long",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_route, read_request, Response, MAX_BODY_SIZE};
+
+    fn request(text: &str) -> Response {
+        read_request(&mut text.as_bytes()).unwrap()
+    }
+
+    fn post(path: &str, body: &str) -> Response {
+        request(&format!(
+            "POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            path,
+            body.len(),
+            body
+        ))
+    }
+
+    #[test]
+    fn routes() {
+        assert_eq!(parse_route("/day/7/part/2"), Some((7, 2)));
+        assert_eq!(parse_route("/day/7/part/2/"), Some((7, 2)));
+        assert_eq!(parse_route("/day/x/part/2"), None);
+        assert_eq!(parse_route("/day/7/part/2/extra"), None);
+        assert_eq!(parse_route("/"), None);
+    }
+
+    #[test]
+    fn answers() {
+        let response = post("/day/1/part/1", "1\n2\n\n4\n");
+
+        assert_eq!(response.status, 200);
+        assert!(
+            response.body.contains("\"answer\":\"4\""),
+            "{}",
+            response.body
+        );
+    }
+
+    #[test]
+    fn malformed_request_line_is_400() {
+        assert_eq!(request("\r\n\r\n").status, 400);
+    }
+
+    #[test]
+    fn unknown_route_or_day_is_404() {
+        assert_eq!(post("/days/1", "").status, 404);
+        assert_eq!(post("/day/99/part/1", "").status, 404);
+    }
+
+    #[test]
+    fn only_post_is_allowed() {
+        assert_eq!(request("GET /day/1/part/1 HTTP/1.1\r\n\r\n").status, 405);
+    }
+
+    #[test]
+    fn oversized_body_is_413() {
+        let text = format!(
+            "POST /day/1/part/1 HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_SIZE + 1
+        );
+
+        assert_eq!(request(&text).status, 413);
+    }
+
+    #[test]
+    fn bad_input_is_422() {
+        let response = post("/day/1/part/1", "one\n");
+
+        assert_eq!(response.status, 422);
+        assert!(response.body.contains("Line 1"), "{}", response.body);
+    }
+
+    #[test]
+    fn solver_panic_is_500() {
+        assert_eq!(post("/day/9/part/1", "X 1\n").status, 500);
+    }
+
+    #[test]
+    fn too_many_headers_is_431() {
+        let text = format!(
+            "POST /day/1/part/1 HTTP/1.1\r\n{}\r\n",
+            "X-A: b\r\n".repeat(200)
+        );
+
+        assert_eq!(request(&text).status, 431);
+    }
+}
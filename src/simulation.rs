@@ -0,0 +1,155 @@
+use std::fmt::Debug;
+
+use crate::{event, trace::Level};
+
+/** A puzzle that advances one discrete step at a time */
+pub trait Simulation {
+    /** Cheap copy of the interesting parts of the state, for logging and assertions */
+    type Snapshot;
+
+    fn step(&mut self);
+
+    fn is_done(&self) -> bool;
+
+    fn snapshot(&self) -> Self::Snapshot;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/** Hooks into a running simulation. Returning `Control::Stop` ends the run early */
+pub trait Observer<S: Simulation> {
+    /** Called with the state the next step will start from */
+    fn before_step(&mut self, _simulation: &S) -> Control {
+        Control::Continue
+    }
+
+    /** Called with the state the last step produced */
+    fn after_step(&mut self, _simulation: &S) -> Control {
+        Control::Continue
+    }
+}
+
+/** Step `simulation` until it's done or an observer stops it, returning the number of steps taken */
+pub fn run<S: Simulation>(simulation: &mut S, observers: &mut [&mut dyn Observer<S>]) -> usize {
+    let mut steps = 0;
+
+    while !simulation.is_done() {
+        let mut control = Control::Continue;
+
+        // Every observer gets to see the step, even if an earlier one asked to stop
+        for observer in observers.iter_mut() {
+            if observer.before_step(simulation) == Control::Stop {
+                control = Control::Stop;
+            }
+        }
+
+        if control == Control::Stop {
+            break;
+        }
+
+        simulation.step();
+        steps += 1;
+
+        for observer in observers.iter_mut() {
+            if observer.after_step(simulation) == Control::Stop {
+                control = Control::Stop;
+            }
+        }
+
+        if control == Control::Stop {
+            break;
+        }
+    }
+
+    steps
+}
+
+/** Emits every snapshot as a trace event */
+pub struct Logger {
+    label: &'static str,
+    steps: usize,
+}
+
+impl Logger {
+    pub fn new(label: &'static str) -> Logger {
+        Logger { label, steps: 0 }
+    }
+}
+
+impl<S: Simulation> Observer<S> for Logger
+where
+    S::Snapshot: Debug,
+{
+    fn after_step(&mut self, simulation: &S) -> Control {
+        self.steps += 1;
+        event!(
+            Level::Trace,
+            "step",
+            label = self.label,
+            step = self.steps,
+            snapshot = simulation.snapshot()
+        );
+
+        Control::Continue
+    }
+}
+
+/** Counts the steps that have been taken */
+#[derive(Default)]
+pub struct Counter {
+    pub steps: usize,
+}
+
+impl<S: Simulation> Observer<S> for Counter {
+    fn after_step(&mut self, _simulation: &S) -> Control {
+        self.steps += 1;
+
+        Control::Continue
+    }
+}
+
+/** Calls `render` after every `every` steps */
+pub struct Render<F> {
+    every: usize,
+    steps: usize,
+    render: F,
+}
+
+impl<F> Render<F> {
+    pub fn new(every: usize, render: F) -> Render<F> {
+        Render {
+            every: every.max(1),
+            steps: 0,
+            render,
+        }
+    }
+}
+
+impl<S: Simulation, F: FnMut(&S)> Observer<S> for Render<F> {
+    fn after_step(&mut self, simulation: &S) -> Control {
+        self.steps += 1;
+
+        if self.steps.is_multiple_of(self.every) {
+            (self.render)(simulation);
+        }
+
+        Control::Continue
+    }
+}
+
+/** Stops the simulation as soon as `predicate` holds after a step */
+pub struct StopWhen<F>(pub F);
+
+impl<S: Simulation, F: FnMut(&S) -> bool> Observer<S> for StopWhen<F> {
+    fn after_step(&mut self, simulation: &S) -> Control {
+        if (self.0)(simulation) {
+            Control::Stop
+        } else {
+            Control::Continue
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::{
+    fmt::Display,
+    panic::{self, AssertUnwindSafe},
+};
+
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /** There's no solver for this day and part */
+    NotImplemented,
+    /** The solver couldn't make sense of the input */
+    InvalidInput(String),
+    /** The solver panicked, which is a bug in it rather than a problem with the input */
+    Panicked(String),
+}
+
+/** Run a single part of a single day, returning its answer as text */
+pub fn solve(day: u32, part: u32, input: &[String]) -> Result<String, SolveError> {
+    let solver: fn(&[String]) -> Result<String, String> = match (day, part) {
+        (1, 1) => |input| {
+            day01::part1(input)
+                .map_err(|err| err.to_string())
+                .and_then(answer)
+        },
+        (1, 2) => |input| {
+            day01::part2(input)
+                .map_err(|err| err.to_string())
+                .and_then(answer)
+        },
+        (2, 1) => |input| day02::part1(input).and_then(answer),
+        (2, 2) => |input| day02::part2(input).and_then(answer),
+        (3, 1) => |input| answer(day03::part1(input)),
+        (3, 2) => |input| answer(day03::part2(input)),
+        (4, 1) => |input| answer(day04::part1(input)),
+        (4, 2) => |input| answer(day04::part2(input)),
+        (5, 1) => |input| {
+            day05::part1(input)
+                .map_err(|err| err.to_string())
+                .and_then(answer)
+        },
+        (5, 2) => |input| {
+            day05::part2(input)
+                .map_err(|err| err.to_string())
+                .and_then(answer)
+        },
+        (6, 1) => |input| day06::part1(input).ok_or_else(no_marker).and_then(answer),
+        (6, 2) => |input| day06::part2(input).ok_or_else(no_marker).and_then(answer),
+        (7, 1) => |input| answer(day07::part1(input)),
+        (7, 2) => |input| answer(day07::part2(input)),
+        (8, 1) => |input| answer(day08::part1(input)),
+        (8, 2) => |input| answer(day08::part2(input)),
+        (9, 1) => |input| answer(day09::part1(input)),
+        (9, 2) => |input| answer(day09::part2(input)),
+        (10, 1) => |input| answer(day10::part1(input)),
+        (10, 2) => |input| answer(day10::part2(input)),
+        (11, 1) => |input| answer(day11::part1(input)),
+        (11, 2) => |input| answer(day11::part2(input)),
+        (12, 1) => |input| answer(day12::part1(input)),
+        (12, 2) => |input| answer(day12::part2(input)),
+        (13, 1) => |input| answer(day13::part1(input)),
+        (13, 2) => |input| answer(day13::part2(input)),
+        (14, 1) => |input| answer(day14::part1(input)),
+        (14, 2) => |input| answer(day14::part2(input)),
+        (15, 1) => |input| answer(day15::part1(input)),
+        (15, 2) => |input| answer(day15::part2(input)),
+        _ => return Err(SolveError::NotImplemented),
+    };
+
+    // Solvers that check their input say so with `Err`, anything else that goes wrong is a bug
+    match panic::catch_unwind(AssertUnwindSafe(|| solver(input))) {
+        Ok(Ok(answer)) => Ok(answer),
+        Ok(Err(message)) => Err(SolveError::InvalidInput(message)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| String::from("Solver panicked"));
+
+            Err(SolveError::Panicked(message))
+        }
+    }
+}
+
+fn answer(value: impl Display) -> Result<String, String> {
+    Ok(value.to_string())
+}
+
+fn no_marker() -> String {
+    String::from("No marker found")
+}
@@ -0,0 +1,68 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/** How chatty diagnostics are, 0 (the default) being silent */
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /** Once per run: timings and totals */
+    Info = 1,
+    /** Once per phase: parsed sizes, iteration counts */
+    Debug = 2,
+    /** Per step: queue lengths, rounds, rendered grids */
+    Trace = 3,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(match self {
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        })
+    }
+}
+
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level as u8
+}
+
+/** Write an event to stderr, keeping stdout for answers. Use `event!` rather than calling this */
+pub fn emit(level: Level, target: &str, name: &str, fields: &[(&str, String)]) {
+    // Drop the crate name, it's the same for every event
+    let target = target.split_once("::").map_or(target, |(_, module)| module);
+    let mut line = format!("[{:<5} {}] {}", level, target, name);
+
+    for (key, value) in fields {
+        line.push_str(&format!(" {}={}", key, value));
+    }
+
+    eprintln!("{}", line);
+}
+
+/**
+ * Emit a structured diagnostic event, if the verbosity allows it.
+ * Field values are `Debug` formatted, and only evaluated when the event is enabled
+ *
+ * `event!(Level::Debug, "parsed", monkeys = monkeys.len());`
+ */
+#[macro_export]
+macro_rules! event {
+    ($level:expr, $name:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        if $crate::trace::enabled($level) {
+            $crate::trace::emit(
+                $level,
+                module_path!(),
+                $name,
+                &[$((stringify!($key), format!("{:?}", $value))),*],
+            );
+        }
+    };
+}